@@ -0,0 +1,381 @@
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+
+use winapi::shared::devpropdef::*;
+use winapi::shared::guiddef::GUID;
+
+use crate::value::GuidWrap;
+
+pub mod names;
+
+/// A type whose values can be safely decoded from the raw value of a property
+///
+/// Used by [`Property::try_fetch()`](crate::devset::Property::try_fetch) to check that
+/// the property actually holds a value of this type before decoding it
+pub trait DevPropTypeTag: Sized {
+    /// The `DEVPROP_TYPE_*` type of the properties holding values of this type
+    const TYPE: DEVPROPTYPE;
+
+    /// Decodes the value from its raw bytes, `None` is returned if their size is wrong
+    fn decode(raw: &[u8]) -> Option<Self>;
+}
+
+macro_rules! impl_dev_prop_type_tag {
+    ($($t:ty => $ty:ident),* $(,)?) => {
+        $(
+            impl DevPropTypeTag for $t {
+                const TYPE: DEVPROPTYPE = $ty;
+
+                fn decode(raw: &[u8]) -> Option<Self> {
+                    Some(Self::from_ne_bytes(raw.try_into().ok()?))
+                }
+            }
+        )*
+    };
+}
+
+impl_dev_prop_type_tag!(
+    i8 => DEVPROP_TYPE_SBYTE,
+    u8 => DEVPROP_TYPE_BYTE,
+    i16 => DEVPROP_TYPE_INT16,
+    u16 => DEVPROP_TYPE_UINT16,
+    i32 => DEVPROP_TYPE_INT32,
+    u32 => DEVPROP_TYPE_UINT32,
+    i64 => DEVPROP_TYPE_INT64,
+    u64 => DEVPROP_TYPE_UINT64,
+    f32 => DEVPROP_TYPE_FLOAT,
+    f64 => DEVPROP_TYPE_DOUBLE,
+);
+
+impl DevPropTypeTag for bool {
+    const TYPE: DEVPROPTYPE = DEVPROP_TYPE_BOOLEAN;
+
+    fn decode(raw: &[u8]) -> Option<Self> {
+        match raw {
+            [v] => Some(*v as i8 == DEVPROP_TRUE),
+            _ => None,
+        }
+    }
+}
+
+impl DevPropTypeTag for GUID {
+    const TYPE: DEVPROPTYPE = DEVPROP_TYPE_GUID;
+
+    fn decode(raw: &[u8]) -> Option<Self> {
+        let raw: &[u8; 16] = raw.try_into().ok()?;
+        Some(GUID {
+            Data1: u32::from_ne_bytes(raw[0..4].try_into().unwrap()),
+            Data2: u16::from_ne_bytes(raw[4..6].try_into().unwrap()),
+            Data3: u16::from_ne_bytes(raw[6..8].try_into().unwrap()),
+            Data4: raw[8..16].try_into().unwrap(),
+        })
+    }
+}
+
+/// A wrapper around the [`DEVPROPKEY`] struct from the [`winapi`] that can be used as key
+/// of maps and sets
+///
+/// Two keys are equal when both their `fmtid` (compared field by field) and `pid` are,
+/// they are ordered by `fmtid` first, and then by `pid`
+#[derive(Clone, Copy)]
+pub struct DevPropKey(pub DEVPROPKEY);
+
+impl DevPropKey {
+    /// Returns the fields of the key in the order they are compared
+    fn fields(&self) -> (u32, u16, u16, [u8; 8], u32) {
+        let DEVPROPKEY { fmtid, pid } = self.0;
+        (fmtid.Data1, fmtid.Data2, fmtid.Data3, fmtid.Data4, pid)
+    }
+
+    /// Parses a key in the `fmtid::pid` form of its [`Display`](core::fmt::Display),
+    /// `None` if the string isn't valid
+    #[cfg(feature = "serde")]
+    pub(crate) fn parse(s: &str) -> Option<Self> {
+        let (fmtid, pid) = s.split_once("::")?;
+        Some(Self(DEVPROPKEY {
+            fmtid: fmtid.parse::<GuidWrap>().ok()?.0,
+            pid: pid.parse().ok()?,
+        }))
+    }
+}
+
+impl PartialEq for DevPropKey {
+    fn eq(&self, other: &Self) -> bool {
+        IsEqualDevPropKey(&self.0, &other.0)
+    }
+}
+
+impl Eq for DevPropKey {}
+
+impl Hash for DevPropKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.fields().hash(state);
+    }
+}
+
+impl PartialOrd for DevPropKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DevPropKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.fields().cmp(&other.fields())
+    }
+}
+
+impl From<DEVPROPKEY> for DevPropKey {
+    fn from(key: DEVPROPKEY) -> Self {
+        Self(key)
+    }
+}
+
+impl From<DevPropKey> for DEVPROPKEY {
+    fn from(key: DevPropKey) -> Self {
+        key.0
+    }
+}
+
+impl core::fmt::Debug for DevPropKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DevPropKey")
+            .field("fmtid", &GuidWrap(self.0.fmtid))
+            .field("pid", &self.0.pid)
+            .finish()
+    }
+}
+
+/// Formats the key as `fmtid::pid`
+impl core::fmt::Display for DevPropKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}::{}", GuidWrap(self.0.fmtid), self.0.pid)
+    }
+}
+
+macro_rules! problem_codes {
+    ($($(#[$doc:meta])* $variant:ident = $code:literal => $name:literal),* $(,)?) => {
+        /// The `CM_PROB_*` problem of a device, the value of `DEVPKEY_Device_ProblemCode`
+        ///
+        /// Returned by [`DevInterfaceData::fetch_problem_code()`](crate::devset::DevInterfaceData::fetch_problem_code),
+        /// only the common problems have their own variant, the others are [`ProblemCode::Unknown`]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum ProblemCode {
+            $( $(#[$doc])* $variant, )*
+            /// A problem without its own variant, the raw code is attached
+            Unknown(u32),
+        }
+
+        impl ProblemCode {
+            /// Returns the problem of the given raw `CM_PROB_*` code
+            pub fn from_code(code: u32) -> Self {
+                match code {
+                    $( $code => Self::$variant, )*
+                    code => Self::Unknown(code),
+                }
+            }
+
+            /// Returns the raw `CM_PROB_*` code of the problem
+            pub fn code(self) -> u32 {
+                match self {
+                    $( Self::$variant => $code, )*
+                    Self::Unknown(code) => code,
+                }
+            }
+
+            /// Returns the name of the `CM_PROB_*` constant of the problem, `None` for unknown ones
+            pub fn name(self) -> Option<&'static str> {
+                match self {
+                    $( Self::$variant => Some($name), )*
+                    Self::Unknown(_) => None,
+                }
+            }
+        }
+    };
+}
+
+// NOTE: `winapi` doesn't define the `CM_PROB_*` constants, the values come from `cfg.h`
+problem_codes!(
+    /// The device is not configured
+    NotConfigured = 1 => "CM_PROB_NOT_CONFIGURED",
+    /// There isn't enough memory to run the device
+    OutOfMemory = 3 => "CM_PROB_OUT_OF_MEMORY",
+    /// The device can't start
+    FailedStart = 10 => "CM_PROB_FAILED_START",
+    /// The device can't find enough free resources it can use
+    NormalConflict = 12 => "CM_PROB_NORMAL_CONFLICT",
+    /// The device can't work properly until the computer is restarted
+    NeedRestart = 14 => "CM_PROB_NEED_RESTART",
+    /// The drivers for the device must be reinstalled
+    Reinstall = 18 => "CM_PROB_REINSTALL",
+    /// The configuration of the device in the registry is incomplete or damaged
+    Registry = 19 => "CM_PROB_REGISTRY",
+    /// The device will be removed
+    WillBeRemoved = 21 => "CM_PROB_WILL_BE_REMOVED",
+    /// The device is disabled
+    Disabled = 22 => "CM_PROB_DISABLED",
+    /// The device is not present or not working properly
+    DeviceNotThere = 24 => "CM_PROB_DEVICE_NOT_THERE",
+    /// The drivers for the device are not installed
+    FailedInstall = 28 => "CM_PROB_FAILED_INSTALL",
+    /// The device is disabled by its firmware
+    HardwareDisabled = 29 => "CM_PROB_HARDWARE_DISABLED",
+    /// The driver of the device failed to load
+    FailedAdd = 31 => "CM_PROB_FAILED_ADD",
+    /// The service of the device is disabled
+    DisabledService = 32 => "CM_PROB_DISABLED_SERVICE",
+    /// The driver of the device failed its initialization
+    FailedDriverEntry = 37 => "CM_PROB_FAILED_DRIVER_ENTRY",
+    /// The driver of the device failed to load
+    DriverFailedLoad = 39 => "CM_PROB_DRIVER_FAILED_LOAD",
+    /// The device reported a failure after it started
+    FailedPostStart = 43 => "CM_PROB_FAILED_POST_START",
+    /// The device is not connected (it was present before)
+    Phantom = 45 => "CM_PROB_PHANTOM",
+    /// The device is prepared for removal
+    HeldForEject = 47 => "CM_PROB_HELD_FOR_EJECT",
+    /// The driver of the device is blocked
+    DriverBlocked = 48 => "CM_PROB_DRIVER_BLOCKED",
+    /// The driver of the device isn't signed
+    UnsignedDriver = 52 => "CM_PROB_UNSIGNED_DRIVER",
+);
+
+/// Formats the problem as the name of its constant, or as its raw code if unknown
+impl core::fmt::Display for ProblemCode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{name}"),
+            None => write!(f, "CM_PROB({})", self.code()),
+        }
+    }
+}
+
+/// A set of `DEVPROP_TYPE_*` base types, without the `DEVPROP_TYPEMOD_*` modifiers
+///
+/// Used by [`DevInterfaceData::properties_of_types()`](crate::devset::DevInterfaceData::properties_of_types)
+/// to select the values to decode, e.g.
+/// `TypeMask::of(DEVPROP_TYPE_STRING) | TypeMask::of(DEVPROP_TYPE_GUID)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TypeMask(u32);
+
+impl TypeMask {
+    /// The mask containing all the base types
+    pub const ALL: Self = Self(u32::MAX);
+
+    /// Returns the mask containing only the base type of the given type (modifiers are ignored)
+    pub const fn of(ty: DEVPROPTYPE) -> Self {
+        // NOTE: all the defined base types are less than 32, the others can't be in a mask
+        match 1u32.checked_shl(ty & DEVPROP_MASK_TYPE) {
+            Some(bit) => Self(bit),
+            None => Self(0),
+        }
+    }
+
+    /// Returns whether or not the base type of the given type is in the mask
+    pub const fn contains(self, ty: DEVPROPTYPE) -> bool {
+        let bit = Self::of(ty).0;
+        bit != 0 && self.0 & bit == bit
+    }
+}
+
+impl core::ops::BitOr for TypeMask {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// Returns a readable name of the given `DEVPROP_TYPE_*` type, modifiers included
+/// (e.g. `"uint32"` for `DEVPROP_TYPE_UINT32` or `"guid[]"` for an array of GUIDs)
+///
+/// `"unknown"` is returned for types that aren't defined
+pub fn type_name(ty: DEVPROPTYPE) -> &'static str {
+    match (ty & DEVPROP_MASK_TYPEMOD, ty & DEVPROP_MASK_TYPE) {
+        (0, DEVPROP_TYPE_EMPTY) => "empty",
+        (0, DEVPROP_TYPE_NULL) => "null",
+        (0, DEVPROP_TYPE_SBYTE) => "int8",
+        (0, DEVPROP_TYPE_BYTE) => "uint8",
+        (0, DEVPROP_TYPE_INT16) => "int16",
+        (0, DEVPROP_TYPE_UINT16) => "uint16",
+        (0, DEVPROP_TYPE_INT32) => "int32",
+        (0, DEVPROP_TYPE_UINT32) => "uint32",
+        (0, DEVPROP_TYPE_INT64) => "int64",
+        (0, DEVPROP_TYPE_UINT64) => "uint64",
+        (0, DEVPROP_TYPE_FLOAT) => "float",
+        (0, DEVPROP_TYPE_DOUBLE) => "double",
+        (0, DEVPROP_TYPE_DECIMAL) => "decimal",
+        (0, DEVPROP_TYPE_GUID) => "guid",
+        (0, DEVPROP_TYPE_CURRENCY) => "currency",
+        (0, DEVPROP_TYPE_DATE) => "date",
+        (0, DEVPROP_TYPE_FILETIME) => "filetime",
+        (0, DEVPROP_TYPE_BOOLEAN) => "boolean",
+        (0, DEVPROP_TYPE_STRING) => "string",
+        (0, DEVPROP_TYPE_SECURITY_DESCRIPTOR) => "security_descriptor",
+        (0, DEVPROP_TYPE_SECURITY_DESCRIPTOR_STRING) => "security_descriptor_string",
+        (0, DEVPROP_TYPE_DEVPROPKEY) => "devpropkey",
+        (0, DEVPROP_TYPE_DEVPROPTYPE) => "devproptype",
+        (0, DEVPROP_TYPE_ERROR) => "error",
+        (0, DEVPROP_TYPE_NTSTATUS) => "ntstatus",
+        (0, DEVPROP_TYPE_STRING_INDIRECT) => "string_indirect",
+        (DEVPROP_TYPEMOD_ARRAY, DEVPROP_TYPE_SBYTE) => "int8[]",
+        (DEVPROP_TYPEMOD_ARRAY, DEVPROP_TYPE_BYTE) => "binary",
+        (DEVPROP_TYPEMOD_ARRAY, DEVPROP_TYPE_INT16) => "int16[]",
+        (DEVPROP_TYPEMOD_ARRAY, DEVPROP_TYPE_UINT16) => "uint16[]",
+        (DEVPROP_TYPEMOD_ARRAY, DEVPROP_TYPE_INT32) => "int32[]",
+        (DEVPROP_TYPEMOD_ARRAY, DEVPROP_TYPE_UINT32) => "uint32[]",
+        (DEVPROP_TYPEMOD_ARRAY, DEVPROP_TYPE_INT64) => "int64[]",
+        (DEVPROP_TYPEMOD_ARRAY, DEVPROP_TYPE_UINT64) => "uint64[]",
+        (DEVPROP_TYPEMOD_ARRAY, DEVPROP_TYPE_FLOAT) => "float[]",
+        (DEVPROP_TYPEMOD_ARRAY, DEVPROP_TYPE_DOUBLE) => "double[]",
+        (DEVPROP_TYPEMOD_ARRAY, DEVPROP_TYPE_DECIMAL) => "decimal[]",
+        (DEVPROP_TYPEMOD_ARRAY, DEVPROP_TYPE_GUID) => "guid[]",
+        (DEVPROP_TYPEMOD_ARRAY, DEVPROP_TYPE_CURRENCY) => "currency[]",
+        (DEVPROP_TYPEMOD_ARRAY, DEVPROP_TYPE_DATE) => "date[]",
+        (DEVPROP_TYPEMOD_ARRAY, DEVPROP_TYPE_FILETIME) => "filetime[]",
+        (DEVPROP_TYPEMOD_ARRAY, DEVPROP_TYPE_BOOLEAN) => "boolean[]",
+        (DEVPROP_TYPEMOD_ARRAY, DEVPROP_TYPE_DEVPROPKEY) => "devpropkey[]",
+        (DEVPROP_TYPEMOD_ARRAY, DEVPROP_TYPE_DEVPROPTYPE) => "devproptype[]",
+        (DEVPROP_TYPEMOD_ARRAY, DEVPROP_TYPE_ERROR) => "error[]",
+        (DEVPROP_TYPEMOD_ARRAY, DEVPROP_TYPE_NTSTATUS) => "ntstatus[]",
+        (DEVPROP_TYPEMOD_LIST, DEVPROP_TYPE_STRING) => "string_list",
+        (DEVPROP_TYPEMOD_LIST, DEVPROP_TYPE_SECURITY_DESCRIPTOR_STRING) => {
+            "security_descriptor_string_list"
+        }
+        (DEVPROP_TYPEMOD_LIST, DEVPROP_TYPE_STRING_INDIRECT) => "string_indirect_list",
+        _ => "unknown",
+    }
+}
+
+/// Returns the `DEVPROP_TYPE_*` type with the given [`type_name()`], `None` for `"unknown"`
+/// or names that aren't returned by it
+#[cfg(feature = "serde")]
+pub(crate) fn type_from_name(name: &str) -> Option<DEVPROPTYPE> {
+    let modifiers = [0, DEVPROP_TYPEMOD_ARRAY, DEVPROP_TYPEMOD_LIST];
+    modifiers
+        .into_iter()
+        .flat_map(|modifier| (0..=DEVPROP_MASK_TYPE).map(move |ty| modifier | ty))
+        .find(|&ty| name != "unknown" && type_name(ty) == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+
+    #[test]
+    fn problem_code_mappings() {
+        assert_eq!(ProblemCode::from_code(22), ProblemCode::Disabled);
+        assert_eq!(ProblemCode::from_code(1), ProblemCode::NotConfigured);
+        // NOTE: 28 is `CM_PROB_FAILED_INSTALL` in `cfg.h`, not `CM_PROB_NOT_CONFIGURED` (1)
+        assert_eq!(ProblemCode::from_code(28), ProblemCode::FailedInstall);
+        assert_eq!(ProblemCode::Disabled.name(), Some("CM_PROB_DISABLED"));
+
+        let unknown = ProblemCode::from_code(9999);
+        assert_eq!(unknown, ProblemCode::Unknown(9999));
+        assert_eq!(unknown.code(), 9999);
+        assert_eq!(unknown.name(), None);
+        assert_eq!(unknown.to_string(), "CM_PROB(9999)");
+    }
+}
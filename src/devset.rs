@@ -1,65 +1,393 @@
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::fs::File;
 use std::iter;
 use std::marker::PhantomData;
-use std::mem::{size_of, size_of_val, zeroed};
+use std::mem::{offset_of, size_of, zeroed};
 use std::ops::Deref;
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+use std::os::windows::io::FromRawHandle;
 use std::ptr::{null, null_mut};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use winapi::ctypes::c_void;
+use winapi::shared::devpkey::*;
 use winapi::shared::devpropdef::*;
 use winapi::shared::ntdef::{FALSE, TRUE};
-use winapi::shared::winerror::{ERROR_INSUFFICIENT_BUFFER, ERROR_NO_MORE_ITEMS};
-use winapi::shared::{guiddef::*, minwindef::DWORD};
-use winapi::um::{errhandlingapi::*, handleapi::*, setupapi::*};
+use winapi::shared::sddl::{ConvertSecurityDescriptorToStringSecurityDescriptorW, SDDL_REVISION_1};
+use winapi::shared::winerror::{FACILITY_WIN32, HRESULT_CODE, HRESULT_FACILITY, S_OK};
+use winapi::shared::{
+    guiddef::*,
+    minwindef::{BOOL, DWORD, UINT, ULONG},
+};
+use winapi::um::fileapi::{CreateFileW, OPEN_EXISTING};
+use winapi::um::securitybaseapi::{GetSecurityDescriptorLength, IsValidSecurityDescriptor};
+use winapi::um::stringapiset::CompareStringOrdinal;
+use winapi::um::winbase::LocalFree;
+use winapi::um::winnt::{
+    DACL_SECURITY_INFORMATION, GROUP_SECURITY_INFORMATION, HRESULT, LPCWSTR, LPWSTR,
+    OWNER_SECURITY_INFORMATION, SACL_SECURITY_INFORMATION, SECURITY_DESCRIPTOR_RELATIVE,
+    SE_SELF_RELATIVE,
+};
+use winapi::um::{handleapi::*, setupapi::*, winioctl::*};
+
+use crate::devprop::{self, DevPropKey, DevPropTypeTag, TypeMask};
+use crate::value::{ole_date_to_unix_secs, FILETIME_TICKS_PER_SEC, FILETIME_UNIX_EPOCH_DAYS};
+use crate::win;
+
+mod watch;
+pub use watch::{watch, DeviceEvent, DeviceWatch};
+
+// NOTE: the data types used to live here, they are re-exported to keep their paths
+#[cfg(feature = "serde")]
+pub use crate::value::Base64Binary;
+pub use crate::value::{
+    DevProperty, DevPropertyConversionError, GuidParseError, GuidWrap, PRINTABLE_TEXT_THRESHOLD,
+};
 
 pub struct DevInterfaceSet {
     handle: HDEVINFO,
 }
 
 impl DevInterfaceSet {
-    fn fetch(additional_flags: DWORD) -> Result<Self, DWORD> {
-        // SAFETY: NULL is allowed for all the parameters
-        // https://docs.microsoft.com/en-gb/windows/win32/api/setupapi/nf-setupapi-setupdigetclassdevsw?redirectedfrom=MSDN#parameters
+    /// Creates a new device set with the given flags
+    ///
+    /// When a class is given only the device interfaces of that class are included,
+    /// otherwise all the classes are.
+    /// When a machine is given the devices of that machine are listed, otherwise the local ones are.
+    fn fetch(
+        class: Option<&GUID>,
+        machine: Option<&str>,
+        additional_flags: DWORD,
+    ) -> win::Result<Self> {
+        let (class, all_classes) = match class {
+            Some(guid) => (guid as *const GUID, 0),
+            None => (null(), DIGCF_ALLCLASSES),
+        };
+        let machine: Option<Vec<u16>> = machine.map(|v| v.encode_utf16().chain([0]).collect());
+        // SAFETY: NULL is allowed for all the parameters, otherwise
+        // `ClassGuid` points to a valid GUID and `MachineName` to a null-terminated UTF-16 string
+        // https://docs.microsoft.com/en-us/windows/win32/api/setupapi/nf-setupapi-setupdigetclassdevsexw#parameters
         let handle = unsafe {
-            SetupDiGetClassDevsW(
-                null(),
+            SetupDiGetClassDevsExW(
+                class,
                 null(),
                 null_mut(),
-                DIGCF_ALLCLASSES | DIGCF_DEVICEINTERFACE | additional_flags,
+                all_classes | DIGCF_DEVICEINTERFACE | additional_flags,
+                null_mut(),
+                machine.as_ref().map_or(null(), |v| v.as_ptr()),
+                null_mut(),
             )
         };
         (handle != INVALID_HANDLE_VALUE)
             .then(|| Self { handle })
-            // SAFETY: how can this be unsafe?
-            .ok_or_else(|| unsafe { GetLastError() })
+            .ok_or_else(win::Error::get)
+    }
+
+    /// Wraps a handle to a device set obtained elsewhere, taking ownership of it
+    ///
+    /// The handle is destroyed with `SetupDiDestroyDeviceInfoList` when the returned value is dropped
+    ///
+    /// # Safety
+    ///
+    /// The handle must be a valid one returned by `SetupDiGetClassDevs*` (or `SetupDiCreateDeviceInfoList*`)
+    /// and it must not be used nor destroyed by anything else for as long as the returned value lives
+    pub unsafe fn from_raw_handle(handle: HDEVINFO) -> Self {
+        Self { handle }
+    }
+
+    /// Returns the handle to the device set, which remains owned by `self`
+    ///
+    /// The handle must not be destroyed, and must not be used after `self` is dropped
+    pub fn as_raw_handle(&self) -> HDEVINFO {
+        self.handle
+    }
+
+    /// Returns a builder to choose which device interfaces the set will contain
+    ///
+    /// By default the set contains all the device interfaces of all the classes on the local machine
+    pub fn builder() -> DevInterfaceSetBuilder {
+        DevInterfaceSetBuilder::default()
     }
 
     /// Creates a new device set containing all the device interface classes currently present
     // TODO: expand
-    pub fn fetch_present() -> Result<Self, DWORD> {
-        Self::fetch(DIGCF_PRESENT)
+    pub fn fetch_present() -> win::Result<Self> {
+        Self::builder().present(true).fetch()
     }
 
     /// Creates a new device set containing all the device interface classes
     // TODO: expand
-    pub fn fetch_all() -> Result<Self, DWORD> {
-        Self::fetch(0)
+    pub fn fetch_all() -> win::Result<Self> {
+        Self::builder().fetch()
+    }
+
+    /// Creates a new device set containing all the device interface classes
+    /// of the current hardware profile, see [`DevInterfaceSetBuilder::profile()`]
+    pub fn fetch_profile() -> win::Result<Self> {
+        Self::builder().profile(true).fetch()
+    }
+
+    /// Creates a new device set containing all the device interface classes currently present
+    /// in the current hardware profile, see [`DevInterfaceSetBuilder::profile()`]
+    pub fn fetch_present_profile() -> win::Result<Self> {
+        Self::builder().present(true).profile(true).fetch()
+    }
+
+    /// Creates a new device set containing only the device interfaces of the given class currently present
+    ///
+    /// This is cheaper than [`Self::fetch_present()`] when only one class is going to be enumerated
+    pub fn fetch_present_for_class(guid: &GUID) -> win::Result<Self> {
+        Self::builder().class(*guid).present(true).fetch()
+    }
+
+    /// Creates a new device set containing only the device interfaces of the given class
+    ///
+    /// This is cheaper than [`Self::fetch_all()`] when only one class is going to be enumerated
+    pub fn fetch_all_for_class(guid: &GUID) -> win::Result<Self> {
+        Self::builder().class(*guid).fetch()
+    }
+
+    /// Creates a new device set containing all the device interface classes currently present
+    /// on the given machine
+    ///
+    /// The caller needs the privileges to access the devices of the remote machine, otherwise
+    /// the error (e.g. [`win::Error::ACCESS_DENIED`]) is returned.
+    /// Note that starting with Windows 8 remote operations are no longer supported by SetupAPI.
+    pub fn fetch_present_on(machine: &str) -> win::Result<Self> {
+        Self::builder().machine(machine).present(true).fetch()
+    }
+
+    /// Creates a new device set containing all the device interface classes of the given machine
+    ///
+    /// See [`Self::fetch_present_on()`] for the requirements
+    pub fn fetch_all_on(machine: &str) -> win::Result<Self> {
+        Self::builder().machine(machine).fetch()
     }
 
     /// Returns an iterator over all the data of the device interfaces listed in the set
     ///
     /// The GUID parameter filters which device interface class will be included
-    pub fn enumerate(
+    pub fn enumerate<'a>(
+        &'a self,
+        guid: &'a GUID,
+    ) -> impl Iterator<Item = win::Result<DevInterfaceData<'a>>> + 'a {
+        // NOTE: the same buffer is overwritten by every call, each item gets a copy of it
+        let mut data = DevInterfaceData::raw_zeroed();
+        (0..DWORD::MAX)
+            .map_while(move |i| DevInterfaceData::fetch_with(self, i, guid, &mut data).transpose())
+    }
+
+    /// Returns an iterator over all the data of the device interfaces listed in the set
+    /// that belong to one of the well-known classes (see [`interface_class_name()`]),
+    /// each one with the GUID of its class
+    ///
+    /// The classes are enumerated one after the other, an error in one of them
+    /// is yielded without stopping the enumeration of the others
+    pub fn enumerate_known(
         &self,
-        guid: GUID,
-    ) -> impl Iterator<Item = Result<DevInterfaceData<'_>, DWORD>> {
-        iter::zip(0.., iter::repeat(DevInterfaceData::raw_zeroed())).map_while(
-            move |(i, mut data)| {
-                unsafe { SetupDiEnumDeviceInterfaces(self.handle, null_mut(), &guid, i, &mut data) }
-                    .eq(&TRUE.into())
-                    .then(|| Some(unsafe { DevInterfaceData::from_raw(self, data) }))
-                    .ok_or_else(|| unsafe { GetLastError() })
-                    .or_else(|err| (err == ERROR_NO_MORE_ITEMS).then(|| None).ok_or(err))
-                    .transpose()
-            },
+    ) -> impl Iterator<Item = win::Result<(GUID, DevInterfaceData<'_>)>> + '_ {
+        INTERFACE_CLASSES.iter().flat_map(move |(_, guid)| {
+            self.enumerate(guid)
+                .map(move |data| data.map(|data| (*guid, data)))
+        })
+    }
+
+    /// Returns whether or not the set lists the device interface with the given path,
+    /// comparing the paths ignoring the case (see [`paths_eq_ignore_case()`])
+    ///
+    /// Only the class embedded in the path is enumerated, or all the well-known classes
+    /// (see [`Self::enumerate_known()`]) if the path can't be parsed (see [`ParsedPath`]).
+    /// This is O(n) over the enumerated interfaces, stopping at the first match.
+    pub fn contains_path(&self, path: &OsStr) -> win::Result<bool> {
+        let wide: Vec<u16> = path.encode_wide().collect();
+        let class = path.to_str().and_then(|path| ParsedPath::parse(path).ok());
+        let interfaces: Box<dyn Iterator<Item = win::Result<DevInterfaceData>>> = match class {
+            Some(ParsedPath { class, .. }) => Box::new(
+                (0..DWORD::MAX)
+                    .map_while(move |i| DevInterfaceData::fetch(self, i, &class.0).transpose()),
+            ),
+            None => Box::new(self.into_iter()),
+        };
+        for data in interfaces {
+            if wide_eq_ignore_case(&data?.fetch_path_wide()?, &wide)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Returns the number of device interfaces of the given class listed in the set
+    ///
+    /// This is cheaper than counting the items of [`Self::enumerate()`], as no [`DevInterfaceData`]
+    /// is constructed, but the first error stops the counting and is returned
+    pub fn count(&self, guid: &GUID) -> win::Result<usize> {
+        let mut data = DevInterfaceData::raw_zeroed();
+        for i in 0..DWORD::MAX {
+            // SAFETY:
+            // https://docs.microsoft.com/en-us/windows/win32/api/setupapi/nf-setupapi-setupdienumdeviceinterfaces#parameters
+            // `DeviceInfoSet`: is a valid handle because of the invariants of Self
+            // `DeviceInfoData`: can be null
+            // `InterfaceClassGuid`: is a valid GUID
+            // `MemberIndex`: any value is allowed (`ERROR_NO_MORE_ITEMS` is returned past the end)
+            // `DeviceInterfaceData`: is a valid SP_DEVICE_INTERFACE_DATA with `cbSize` initialized
+            let result =
+                unsafe { SetupDiEnumDeviceInterfaces(self.handle, null_mut(), guid, i, &mut data) };
+            if result != BOOL::from(TRUE) {
+                return match win::Error::get() {
+                    win::Error::NO_MORE_ITEMS => Ok(i as usize),
+                    err => Err(err),
+                };
+            }
+        }
+        // NOTE: the index is a DWORD, so no more than this can be listed
+        Ok(DWORD::MAX as usize)
+    }
+
+    /// Returns an iterator over the data of the device interfaces listed in the set
+    /// for which the predicate returns `true`
+    ///
+    /// The GUID parameter filters which device interface class will be included,
+    /// errors are always yielded.
+    ///
+    /// ```no_run
+    /// # use sd_formatter::devset::{DevInterfaceSet, DevProperty};
+    /// # use winapi::shared::devpkey::DEVPKEY_Storage_Removable_Media;
+    /// # use winapi::um::winioctl::GUID_DEVINTERFACE_DISK;
+    /// let devset = DevInterfaceSet::fetch_present()?;
+    /// let removable = devset.enumerate_filtered(&GUID_DEVINTERFACE_DISK, |data| {
+    ///     matches!(
+    ///         data.fetch_property_value(DEVPKEY_Storage_Removable_Media),
+    ///         Ok(DevProperty::Bool(true))
+    ///     )
+    /// });
+    /// for data in removable {
+    ///     println!("{:?}", data?.fetch_path()?);
+    /// }
+    /// # Ok::<(), sd_formatter::Error>(())
+    /// ```
+    pub fn enumerate_filtered<'a>(
+        &'a self,
+        guid: &'a GUID,
+        mut predicate: impl FnMut(&DevInterfaceData) -> bool + 'a,
+    ) -> impl Iterator<Item = win::Result<DevInterfaceData<'a>>> + 'a {
+        self.enumerate(guid)
+            .filter(move |data| data.as_ref().map_or(true, &mut predicate))
+    }
+}
+
+/// A builder for a [`DevInterfaceSet`], returned by [`DevInterfaceSet::builder()`]
+#[derive(Debug, Clone, Default)]
+pub struct DevInterfaceSetBuilder {
+    /// The class of the device interfaces to include, all of them if `None`
+    class: Option<GuidWrap>,
+    /// The name of the machine to list the devices of, the local one if `None`
+    machine: Option<String>,
+    /// Whether to include only the device interfaces currently present (`DIGCF_PRESENT`)
+    present: bool,
+    /// Whether to include only the device interfaces of the current hardware profile (`DIGCF_PROFILE`)
+    profile: bool,
+}
+
+impl DevInterfaceSetBuilder {
+    /// Includes only the device interfaces currently present if `true` (`DIGCF_PRESENT`)
+    pub fn present(mut self, present: bool) -> Self {
+        self.present = present;
+        self
+    }
+
+    /// Includes only the device interfaces of the current hardware profile if `true` (`DIGCF_PROFILE`)
+    ///
+    /// A hardware profile is a named set of enabled devices, selected at boot.
+    /// Since Windows Vista there's only one profile, so this excludes only the devices
+    /// that were disabled in it (e.g. in older installations that kept their profiles)
+    pub fn profile(mut self, profile: bool) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Includes only the device interfaces of the given class, instead of all of them
+    ///
+    /// This is cheaper when only one class is going to be enumerated
+    pub fn class(mut self, guid: GUID) -> Self {
+        self.class = Some(GuidWrap(guid));
+        self
+    }
+
+    /// Lists the devices of the given machine instead of the local one
+    ///
+    /// See [`DevInterfaceSet::fetch_present_on()`] for the requirements
+    pub fn machine(mut self, machine: &str) -> Self {
+        self.machine = Some(machine.to_owned());
+        self
+    }
+
+    /// Creates the device set and fetches the owned data of all its device interfaces
+    /// of the given class, starting over with a new set when they change in the meantime
+    ///
+    /// When a device is added or removed during the scan `SetupDiEnumDeviceInterfaces` may fail
+    /// with [`win::Error::INVALID_DATA`] or [`win::Error::NO_SUCH_DEVICE_INTERFACE`],
+    /// these errors make the scan restart with a new set up to `retries` times,
+    /// after which the error is returned.
+    /// The errors of fetching the data of the enumerated devices are returned right away.
+    /// This retries the transient races but can't guarantee a perfectly consistent snapshot,
+    /// e.g. a device that is added and removed between two calls goes unnoticed.
+    ///
+    /// This is a method of the builder, not of [`DevInterfaceSet`], because the set
+    /// must be fetched again with the same options to restart the scan
+    pub fn enumerate_stable(
+        &self,
+        guid: &GUID,
+        retries: usize,
+    ) -> win::Result<Vec<OwnedDevInterface>> {
+        let mut attempts = 0;
+        'scan: loop {
+            let set = self.fetch()?;
+            let mut devices = Vec::new();
+            for data in set.enumerate(guid) {
+                // NOTE: only the failures of the enumeration are retried, the ones of fetching
+                //       the data of a device (e.g. decoding its path) would just happen again
+                let data = match data {
+                    Ok(data) => data,
+                    Err(win::Error::INVALID_DATA | win::Error::NO_SUCH_DEVICE_INTERFACE)
+                        if attempts < retries =>
+                    {
+                        attempts += 1;
+                        continue 'scan;
+                    }
+                    Err(err) => return Err(err),
+                };
+                devices.push(data.fetch_owned()?);
+            }
+            return Ok(devices);
+        }
+    }
+
+    /// Creates the device set
+    pub fn fetch(&self) -> win::Result<DevInterfaceSet> {
+        let mut flags = 0;
+        if self.present {
+            flags |= DIGCF_PRESENT;
+        }
+        if self.profile {
+            flags |= DIGCF_PROFILE;
+        }
+        let class = self.class.as_ref().map(|guid| &guid.0);
+        DevInterfaceSet::fetch(class, self.machine.as_deref(), flags)
+    }
+}
+
+/// Iterates over the device interfaces of all the well-known classes,
+/// see [`DevInterfaceSet::enumerate_known()`]
+impl<'a> IntoIterator for &'a DevInterfaceSet {
+    type Item = win::Result<DevInterfaceData<'a>>;
+    type IntoIter = Box<dyn Iterator<Item = Self::Item> + 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(
+            self.enumerate_known()
+                .map(|data| data.map(|(_, data)| data)),
         )
     }
 }
@@ -93,6 +421,10 @@ pub struct DevInterfaceData<'a> {
     handle: HDEVINFO,
     /// The data returned by the [`SetupDiEnumDeviceInterfaces`] function
     data: SP_DEVICE_INTERFACE_DATA,
+    /// The property keys, fetched by the first call to [`Self::cached_property_keys()`]
+    ///
+    /// A [`OnceCell`] is enough as the value is not [`Sync`] anyway (because of the handle)
+    keys: OnceCell<Box<[DEVPROPKEY]>>,
     /// A ghost reference to the device set wrapper, to take advantage of the borrow checker
     _marker: PhantomData<&'a DevInterfaceSet>,
 }
@@ -113,33 +445,110 @@ impl DevInterfaceData<'_> {
     ///
     /// # Safety
     ///
-    /// The values must comply to the invariants of the wrapper: [`Self`]
+    /// The values must comply to the invariants of the wrapper: [`Self`],
+    /// that is `data` must have been filled by a call to [`SetupDiEnumDeviceInterfaces()`]
+    /// to which the handle of `set` was given
     unsafe fn from_raw(set: &DevInterfaceSet, data: SP_DEVICE_INTERFACE_DATA) -> Self {
         Self {
             handle: set.handle,
             data,
+            keys: OnceCell::new(),
             _marker: PhantomData,
         }
     }
 
+    /// Returns the data of the device interface at the given index among the ones of the given class
+    /// listed in the set
+    ///
+    /// `Ok(None)` is returned when the index is past the end (`ERROR_NO_MORE_ITEMS`),
+    /// so this can be used to resume an enumeration from a known index,
+    /// [`DevInterfaceSet::enumerate()`] does the same starting from 0
+    ///
+    /// ```no_run
+    /// # use sd_formatter::devset::{DevInterfaceData, DevInterfaceSet};
+    /// # use winapi::um::winioctl::GUID_DEVINTERFACE_DISK;
+    /// let set = DevInterfaceSet::fetch_present()?;
+    /// let mut index = 0;
+    /// while let Some(data) = DevInterfaceData::fetch(&set, index, &GUID_DEVINTERFACE_DISK)? {
+    ///     println!("{index}: {data}");
+    ///     index += 1;
+    /// }
+    /// # Ok::<(), sd_formatter::Error>(())
+    /// ```
+    pub fn fetch<'a>(
+        set: &'a DevInterfaceSet,
+        index: u32,
+        guid: &GUID,
+    ) -> win::Result<Option<DevInterfaceData<'a>>> {
+        Self::fetch_with(set, index, guid, &mut Self::raw_zeroed())
+    }
+
+    /// Same as [`Self::fetch()`], but the data is retrieved into the given buffer,
+    /// which must come from [`Self::raw_zeroed()`] so that its `cbSize` is initialized
+    ///
+    /// This lets [`DevInterfaceSet::enumerate()`] reuse the same buffer for every index
+    fn fetch_with<'a>(
+        set: &'a DevInterfaceSet,
+        index: u32,
+        guid: &GUID,
+        data: &mut SP_DEVICE_INTERFACE_DATA,
+    ) -> win::Result<Option<DevInterfaceData<'a>>> {
+        // SAFETY:
+        // https://docs.microsoft.com/en-us/windows/win32/api/setupapi/nf-setupapi-setupdienumdeviceinterfaces#parameters
+        // `DeviceInfoSet`: is a valid handle because of the invariants of DevInterfaceSet
+        // `DeviceInfoData`: can be null
+        // `InterfaceClassGuid`: is a valid GUID
+        // `MemberIndex`: any value is allowed (`ERROR_NO_MORE_ITEMS` is returned past the end)
+        // `DeviceInterfaceData`: is a valid SP_DEVICE_INTERFACE_DATA with `cbSize` initialized
+        let result =
+            unsafe { SetupDiEnumDeviceInterfaces(set.handle, null_mut(), guid, index, data) };
+        if result == BOOL::from(TRUE) {
+            // SAFETY: the data has just been retrieved from the handle of the set
+            Ok(Some(unsafe { DevInterfaceData::from_raw(set, *data) }))
+        } else {
+            match win::Error::get() {
+                win::Error::NO_MORE_ITEMS => Ok(None),
+                err => Err(err),
+            }
+        }
+    }
+
+    /// Returns the GUID of the class of the device interface described by this data
+    pub fn class_guid(&self) -> GUID {
+        self.data.InterfaceClassGuid
+    }
+
+    /// Returns the name of the class of the device interface described by this data,
+    /// see [`interface_class_name()`]
+    pub fn class_name(&self) -> Option<&'static str> {
+        interface_class_name(&self.class_guid())
+    }
+
+    /// Returns the status flags of the device interface described by this data
+    pub fn flags(&self) -> InterfaceFlags {
+        InterfaceFlags(self.data.Flags)
+    }
+
     /// Returns whether or not the device interface described by this data is active
     pub fn is_active(&self) -> bool {
-        (self.data.Flags & SPINT_ACTIVE) == SPINT_ACTIVE
+        self.flags().is_active()
     }
 
     /// Returns whether or not the device interface described by this data is the default for it's class
     pub fn is_default(&self) -> bool {
-        (self.data.Flags & SPINT_DEFAULT) == SPINT_DEFAULT
+        self.flags().is_default()
     }
 
     /// Returns whether or not the device interface described by this data is removed
     // TODO: what does it mean for it to be removed?
     pub fn is_removed(&self) -> bool {
-        (self.data.Flags & SPINT_REMOVED) == SPINT_REMOVED
+        self.flags().is_removed()
     }
 
     /// Returns the path of the device interface described by this data instance
-    pub fn fetch_path(&self) -> Result<Vec<u8>, DWORD> {
+    ///
+    /// The path is returned as the raw bytes of its UTF-16 encoding, without the null-terminator
+    pub fn fetch_path(&self) -> win::Result<Vec<u8>> {
         let mut raw_size = 0;
 
         // SAFETY:
@@ -163,38 +572,36 @@ impl DevInterfaceData<'_> {
         };
         // NOTE: this is expected to fail because of DeviceInterfaceDetailDataSize = 0
         //       and, for the same reason, the error is expected to be `ERROR_INSUFFICIENT_BUFFER`
-        assert_eq!(result, FALSE.into());
-        // SAFETY: how can this be unsafe?
-        match unsafe { GetLastError() } {
-            ERROR_INSUFFICIENT_BUFFER => (), // Ok
+        if result != BOOL::from(FALSE) {
+            return Err(win::Error::INTERNAL_ERROR);
+        }
+        match win::Error::get() {
+            win::Error::INSUFFICIENT_BUFFER => (), // Ok
             err => return Err(err),
         }
 
         let raw_usize = raw_size.try_into().unwrap();
-        assert!(raw_usize >= size_of::<SP_DEVICE_INTERFACE_DETAIL_DATA_W>());
-
-        let mut raw = vec![0u8; raw_usize];
-        // SAFETY:
-        // the size check for the structure have been made right above this line
-        // tramsmuting between an array of bytes and a struct it's allowed
-        // TODO: other things? don't know
-        let (prefix, details, _) =
-            unsafe { raw.align_to_mut::<SP_DEVICE_INTERFACE_DETAIL_DATA_W>() };
-
-        assert!(prefix.is_empty());
-        assert!(!details.is_empty());
+        if raw_usize < size_of::<SP_DEVICE_INTERFACE_DETAIL_DATA_W>() {
+            return Err(win::Error::INVALID_DATA);
+        }
 
-        let details = &mut details[0];
-        details.cbSize = size_of_val(details).try_into().unwrap();
+        // NOTE: the buffer is viewed as the struct, so it must be aligned for it, and the part
+        //       not written by the WinAPI must be initialized, so it's zeroed
+        // SAFETY: the struct can be zero initialized
+        let mut details =
+            unsafe { try_alloc_zeroed::<SP_DEVICE_INTERFACE_DETAIL_DATA_W>(raw_usize)? };
+        details[0].cbSize = size_of::<SP_DEVICE_INTERFACE_DETAIL_DATA_W>()
+            .try_into()
+            .unwrap();
 
         // SAFETY:
         // https://docs.microsoft.com/en-us/windows/win32/api/setupapi/nf-setupapi-setupdigetdeviceinterfacedetailw#parameters
         // `DeviceInfoSet`: is a valid handle because of the invariants of Self
         // `DeviceInterfaceData`: is correctly initialized because of the invariants of Self
         // `DeviceInterfaceDetailData`: is a valid SP_DEVICE_INTERFACE_DETAIL_DATA_W
-        //                              with the required size (allocated just aboce here)
+        //                              with the required size (allocated just above here)
         // `DeviceInterfaceDetailDataSize`: is the required size returned from the previous call,
-        //                                  and the size of the actual SP_DEVICE_INTERFACE_DETAIL_DATA_W
+        //                                  and at most the size of the allocated buffer
         // `RequiredSize`: can always be null
         // `DeviceInfoData`: can always be null
         let result = unsafe {
@@ -202,25 +609,109 @@ impl DevInterfaceData<'_> {
                 self.handle,
                 // NOTE: for some obscure reason this wants a *mut T even tho it doesn't modify the value
                 &mut SP_DEVICE_INTERFACE_DATA { ..self.data },
-                details,
+                details.as_mut_ptr(),
                 raw_size,
                 null_mut(),
                 null_mut(),
             )
         };
-        if result != TRUE.into() {
-            // SAFETY: how can this be unsafe?
-            return Err(unsafe { GetLastError() });
-        }
-        // NOTE: from now on details can't be accessed, this is why the raw buffer can be modified
-        //       without taking care of the struct layout
-        let fixed_size_part_size = size_of_val(details) - size_of_val(&details.DevicePath);
-        raw.copy_within(fixed_size_part_size..raw_usize, 0);
-        raw.truncate(raw_usize - fixed_size_part_size);
+        if result != BOOL::from(TRUE) {
+            return Err(win::Error::get());
+        }
+        // SAFETY: the buffer is fully initialized and bytes have no alignment requirements
+        let raw = unsafe { details.align_to::<u8>() }.1;
+        // NOTE: the path starts at the offset of `DevicePath`, which is not necessarily
+        //       the size of the struct minus the size of `DevicePath` because of padding
+        let path_offset = offset_of!(SP_DEVICE_INTERFACE_DETAIL_DATA_W, DevicePath);
+        let mut raw = raw[path_offset..raw_usize].to_vec();
+        // NOTE: the path is null-terminated, but only its content is returned
+        if raw.ends_with(&[0, 0]) {
+            raw.truncate(raw.len() - 2);
+        }
         Ok(raw)
     }
 
-    pub fn fetch_property_keys(&self) -> Result<Vec<DEVPROPKEY>, DWORD> {
+    /// Returns the path of the device interface as UTF-16 code units, without the null-terminator
+    fn fetch_path_wide(&self) -> win::Result<Vec<u16>> {
+        Ok(self
+            .fetch_path()?
+            .chunks_exact(2)
+            .map(|v| u16::from_ne_bytes([v[0], v[1]]))
+            .collect())
+    }
+
+    /// Returns the path of the device interface as an [`OsString`]
+    ///
+    /// Unlike converting it to a [`String`], this preserves the exact encoding of the path
+    /// (unpaired surrogates included) so it can be handed back to the Windows APIs
+    pub fn fetch_path_os(&self) -> win::Result<OsString> {
+        Ok(OsString::from_wide(&self.fetch_path_wide()?))
+    }
+
+    /// Returns the path of the device interface as a [`String`]
+    ///
+    /// [`win::Error::INVALID_DATA`] is returned if the path isn't valid UTF-16,
+    /// see [`Self::fetch_path_os()`] to get it anyway
+    pub fn fetch_path_utf8(&self) -> win::Result<String> {
+        String::from_utf16(&self.fetch_path_wide()?).map_err(|_| win::Error::INVALID_DATA)
+    }
+
+    /// Returns whether or not the path of the device interface is the given one,
+    /// ignoring the case of the letters like Windows does, see [`paths_eq_ignore_case()`]
+    ///
+    /// Useful to match a device against a path saved in a previous run
+    pub fn path_eq_ignore_case(&self, other: &OsStr) -> win::Result<bool> {
+        wide_eq_ignore_case(
+            &self.fetch_path_wide()?,
+            &other.encode_wide().collect::<Vec<_>>(),
+        )
+    }
+
+    /// Returns the components of the path of the device interface, see [`ParsedPath`]
+    ///
+    /// This gives the class of the interface without fetching any property,
+    /// [`win::Error::INVALID_DATA`] is returned if the path isn't valid UTF-16
+    /// or doesn't have the expected form
+    pub fn parse_path(&self) -> win::Result<ParsedPath> {
+        ParsedPath::parse(&self.fetch_path_utf8()?)
+    }
+
+    /// Opens the device interface, returning an owned handle to it
+    ///
+    /// The access and share modes are the `dwDesiredAccess` and `dwShareMode` parameters of
+    /// [`CreateFileW()`], e.g. `GENERIC_READ` and `FILE_SHARE_READ | FILE_SHARE_WRITE`,
+    /// or `0` and `0` to only query the device metadata (like with some IOCTLs)
+    pub fn open(&self, access: u32, share: u32) -> win::Result<File> {
+        let mut path = self.fetch_path_wide()?;
+        path.push(0);
+
+        // SAFETY:
+        // https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-createfilew#parameters
+        // `lpFileName`: is a valid null-terminated UTF-16 string
+        // `dwDesiredAccess` and `dwShareMode`: any value is allowed (wrong ones make the function fail)
+        // `lpSecurityAttributes`: can be null
+        // `dwCreationDisposition`: devices must be opened with `OPEN_EXISTING`
+        // `dwFlagsAndAttributes`: 0 is a valid value
+        // `hTemplateFile`: can be null
+        let handle = unsafe {
+            CreateFileW(
+                path.as_ptr(),
+                access,
+                share,
+                null_mut(),
+                OPEN_EXISTING,
+                0,
+                null_mut(),
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(win::Error::get());
+        }
+        // SAFETY: the handle was just opened and it's not owned by anything else
+        Ok(unsafe { File::from_raw_handle(handle.cast()) })
+    }
+
+    pub fn fetch_property_keys(&self) -> win::Result<Vec<DEVPROPKEY>> {
         let mut size = 0;
 
         // SAFETY:
@@ -243,15 +734,16 @@ impl DevInterfaceData<'_> {
         };
         // NOTE: this is expected to fail because of DeviceInterfaceDetailDataSize = 0
         //       and, for the same reason, the error is expected to be `ERROR_INSUFFICIENT_BUFFER`
-        assert_eq!(result, FALSE.into());
-        // SAFETY: how can this be unsafe?
-        match unsafe { GetLastError() } {
-            ERROR_INSUFFICIENT_BUFFER => (), // Ok
+        if result != BOOL::from(FALSE) {
+            return Err(win::Error::INTERNAL_ERROR);
+        }
+        match win::Error::get() {
+            win::Error::INSUFFICIENT_BUFFER => (), // Ok
             err => return Err(err),
         }
 
         // SAFETY: the DEVPROPKEY struct can be zero initialized
-        let mut properties = vec![unsafe { zeroed() }; size as usize];
+        let mut properties = try_alloc(size as usize, unsafe { zeroed() })?;
 
         // SAFETY:
         // https://docs.microsoft.com/en-us/windows/win32/api/setupapi/nf-setupapi-setupdigetdeviceinterfacepropertykeys#parameters
@@ -271,49 +763,166 @@ impl DevInterfaceData<'_> {
                 0,
             )
         };
-        if result != TRUE.into() {
-            // SAFETY: how can this be unsafe?
-            return Err(unsafe { GetLastError() });
+        if result != BOOL::from(TRUE) {
+            return Err(win::Error::get());
         }
         Ok(properties)
     }
 
-    pub fn fetch_property_value(&self, property: DEVPROPKEY) -> Result<DevProperty, DWORD> {
-        let mut prop_ty = 0;
+    /// Returns the keys of the properties of the device interface, fetching them only the first time
+    ///
+    /// Use [`Self::fetch_property_keys()`] to always get the current ones
+    pub fn cached_property_keys(&self) -> win::Result<&[DEVPROPKEY]> {
+        if let Some(keys) = self.keys.get() {
+            return Ok(keys);
+        }
+        let keys = self.fetch_property_keys()?.into_boxed_slice();
+        Ok(self.keys.get_or_init(|| keys))
+    }
+
+    /// Calls `SetupDiGetDeviceInterfacePropertyW`, or `SetupDiGetDevicePropertyW`
+    /// when the device instance is given, with the given arguments
+    ///
+    /// # Safety
+    ///
+    /// `buffer` must be valid for writes of `size` bytes (or null if `size` is 0)
+    /// and `required_size` must either be null or valid for writes
+    unsafe fn get_property(
+        &self,
+        device: Option<SP_DEVINFO_DATA>,
+        key: &DEVPROPKEY,
+        ty: &mut DEVPROPTYPE,
+        buffer: *mut u8,
+        size: DWORD,
+        required_size: *mut DWORD,
+    ) -> BOOL {
+        match device {
+            None => SetupDiGetDeviceInterfacePropertyW(
+                self.handle,
+                &mut SP_DEVICE_INTERFACE_DATA { ..self.data },
+                key,
+                ty,
+                buffer,
+                size,
+                required_size,
+                0,
+            ),
+            Some(mut device) => SetupDiGetDevicePropertyW(
+                self.handle,
+                &mut device,
+                key,
+                ty,
+                buffer,
+                size,
+                required_size,
+                0,
+            ),
+        }
+    }
+
+    /// Fetches the type and the size of the value of the given property of the device interface,
+    /// or of the device instance when it's given
+    fn fetch_info(
+        &self,
+        device: Option<SP_DEVINFO_DATA>,
+        key: DEVPROPKEY,
+    ) -> win::Result<Property<'_>> {
+        let mut ty = 0;
         let mut size = 0;
 
         // SAFETY:
         // https://docs.microsoft.com/en-us/windows/win32/api/setupapi/nf-setupapi-setupdigetdeviceinterfacepropertyw#parameters
+        // https://docs.microsoft.com/en-us/windows/win32/api/setupapi/nf-setupapi-setupdigetdevicepropertyw#parameters
         // `DeviceInfoSet`: is a valid handle because of the invariants of Self
         // `DeviceInterfaceData`: is correctly initialized because of the invariants of Self
+        // `DeviceInfoData`: is the one returned by `fetch_device_info`
         // `PropertyKey`: any value is allowed (if the property is wrong an error is returned)
         // `PropertyType`: a valid pointer to a `DEVPROPTYPE`
         // `PropertyBuffer`: can be null if `PropertyBufferSize` is 0
         // `PropertyBufferSize`: must be 0 if `PropertyBuffer` is null
         // `RequiredSize`: is a valid pointer to a `DWORD`
         // `Flags`: must be 0
+        let result = unsafe { self.get_property(device, &key, &mut ty, null_mut(), 0, &mut size) };
+        // NOTE: this is expected to fail because of PropertyBufferSize = 0 and, for the same reason,
+        //       the error is expected to be `ERROR_INSUFFICIENT_BUFFER`, unless the value is empty
+        if result == BOOL::from(TRUE) {
+            size = 0;
+        } else {
+            match win::Error::get() {
+                win::Error::INSUFFICIENT_BUFFER => (), // Ok
+                err => return Err(err),
+            }
+        }
+        Ok(Property {
+            data: self,
+            device,
+            key,
+            ty,
+            size,
+        })
+    }
+
+    /// Fetches and decodes the value of the given property of the device interface,
+    /// or of the device instance when it's given
+    ///
+    /// The value is first fetched into a small buffer on the stack, which is enough for most of them
+    /// and saves the size probe, only larger values are fetched into a buffer of the required size
+    fn fetch_value(
+        &self,
+        device: Option<SP_DEVINFO_DATA>,
+        key: DEVPROPKEY,
+    ) -> win::Result<DevProperty> {
+        let mut ty = 0;
+        let mut size = 0;
+        let mut buffer = [0u8; OPTIMISTIC_BUFFER_SIZE];
+
+        // SAFETY:
+        // https://docs.microsoft.com/en-us/windows/win32/api/setupapi/nf-setupapi-setupdigetdeviceinterfacepropertyw#parameters
+        // https://docs.microsoft.com/en-us/windows/win32/api/setupapi/nf-setupapi-setupdigetdevicepropertyw#parameters
+        // `DeviceInfoSet`: is a valid handle because of the invariants of Self
+        // `DeviceInterfaceData`: is correctly initialized because of the invariants of Self
+        // `DeviceInfoData`: is the one returned by `fetch_device_info`
+        // `PropertyKey`: any value is allowed (if the property is wrong an error is returned)
+        // `PropertyType`: a valid pointer to a `DEVPROPTYPE`
+        // `PropertyBuffer`: is a valid buffer of `PropertyBufferSize` bytes
+        // `PropertyBufferSize`: is the size of the buffer
+        // `RequiredSize`: is a valid pointer to a `DWORD`
+        // `Flags`: must be 0
         let result = unsafe {
-            SetupDiGetDeviceInterfacePropertyW(
-                self.handle,
-                &mut SP_DEVICE_INTERFACE_DATA { ..self.data },
-                &property,
-                &mut prop_ty,
-                null_mut(),
-                0,
+            self.get_property(
+                device,
+                &key,
+                &mut ty,
+                buffer.as_mut_ptr(),
+                buffer.len() as DWORD,
                 &mut size,
-                0,
             )
         };
-        // NOTE: this is expected to fail because of DeviceInterfaceDetailDataSize = 0
-        //       and, for the same reason, the error is expected to be `ERROR_INSUFFICIENT_BUFFER`
-        assert_eq!(result, FALSE.into());
-        // SAFETY: how can this be unsafe?
-        match unsafe { GetLastError() } {
-            ERROR_INSUFFICIENT_BUFFER => (), // Ok
-            err => return Err(err),
+        if result == BOOL::from(TRUE) {
+            let raw = buffer
+                .get(..size as usize)
+                .ok_or(win::Error::INVALID_DATA)?;
+            return decode_value(ty, raw.to_vec(), false);
+        }
+        match win::Error::get() {
+            // NOTE: the type and the required size are known now, so there's no need to probe them
+            win::Error::INSUFFICIENT_BUFFER => Property {
+                data: self,
+                device,
+                key,
+                ty,
+                size,
+            }
+            .fetch_value(),
+            err => Err(err),
         }
+    }
 
-        let mut raw = vec![0u8; size as usize];
+    /// Returns whether or not the given property is set on the device interface,
+    /// without fetching its value
+    pub fn has_property(&self, key: &DEVPROPKEY) -> win::Result<bool> {
+        let mut ty = 0;
+        let mut size = 0;
 
         // SAFETY:
         // https://docs.microsoft.com/en-us/windows/win32/api/setupapi/nf-setupapi-setupdigetdeviceinterfacepropertyw#parameters
@@ -325,183 +934,2107 @@ impl DevInterfaceData<'_> {
         // `PropertyBufferSize`: must be 0 if `PropertyBuffer` is null
         // `RequiredSize`: is a valid pointer to a `DWORD`
         // `Flags`: must be 0
+        let result = unsafe { self.get_property(None, key, &mut ty, null_mut(), 0, &mut size) };
+        // NOTE: empty values fit in the empty buffer
+        if result == BOOL::from(TRUE) {
+            return Ok(true);
+        }
+        match win::Error::get() {
+            win::Error::INSUFFICIENT_BUFFER => Ok(true),
+            win::Error::NOT_FOUND => Ok(false),
+            err => Err(err),
+        }
+    }
+
+    /// Fetches the type and the size of the value of the given property, without fetching the value
+    ///
+    /// Empty values (like `DEVPROP_TYPE_EMPTY` or `DEVPROP_TYPE_NULL` ones) have a size of 0,
+    /// while [`win::Error::NOT_FOUND`] is returned if the property is not set at all
+    pub fn fetch_property_info(&self, key: DEVPROPKEY) -> win::Result<Property<'_>> {
+        self.fetch_info(None, key)
+    }
+
+    /// Fetches the value of the given property and decodes it according to its type
+    ///
+    /// [`win::Error::NOT_FOUND`] is returned if the property is not set,
+    /// see [`Self::has_property()`] to only check that.
+    /// This is a shorthand for [`Self::fetch_property_info()`] followed by [`Property::fetch_value()`]
+    pub fn fetch_property_value(&self, property: DEVPROPKEY) -> win::Result<DevProperty> {
+        self.fetch_property_info(property)?.fetch_value()
+    }
+
+    /// Returns the data of the device instance (devnode) that exposes this device interface
+    pub fn fetch_device_info(&self) -> win::Result<SP_DEVINFO_DATA> {
+        let mut device = SP_DEVINFO_DATA {
+            cbSize: size_of::<SP_DEVINFO_DATA>().try_into().unwrap(),
+            // SAFETY: this struct can be zero initialized
+            ..unsafe { zeroed() }
+        };
+
+        // SAFETY:
+        // https://docs.microsoft.com/en-us/windows/win32/api/setupapi/nf-setupapi-setupdigetdeviceinterfacedetailw#parameters
+        // `DeviceInfoSet`: is a valid handle because of the invariants of Self
+        // `DeviceInterfaceData`: is correctly initialized because of the invariants of Self
+        // `DeviceInterfaceDetailData`: must be null if DeviceInterfaceDetailDataSize is 0
+        // `DeviceInterfaceDetailDataSize`: can be 0
+        // `RequiredSize`: can always be null
+        // `DeviceInfoData`: is a valid SP_DEVINFO_DATA with the `cbSize` field initialized
         let result = unsafe {
-            SetupDiGetDeviceInterfacePropertyW(
+            SetupDiGetDeviceInterfaceDetailW(
                 self.handle,
                 &mut SP_DEVICE_INTERFACE_DATA { ..self.data },
-                &property,
-                &mut prop_ty,
-                raw.as_mut_ptr(),
-                size,
                 null_mut(),
                 0,
+                null_mut(),
+                &mut device,
             )
         };
-        if result != TRUE.into() {
-            // SAFETY: how can this be unsafe?
-            return Err(unsafe { GetLastError() });
-        }
-
-        use DevProperty as P;
-
-        let i16conv = |v: &[u8]| i16::from_ne_bytes([v[0], v[1]]);
-        let u16conv = |v: &[u8]| u16::from_ne_bytes([v[0], v[1]]);
-        let i32conv = |v: &[u8]| i32::from_ne_bytes(v[0..4].try_into().unwrap());
-        let u32conv = |v: &[u8]| u32::from_ne_bytes(v[0..4].try_into().unwrap());
-        let i64conv = |v: &[u8]| i64::from_ne_bytes(v[0..8].try_into().unwrap());
-        let u64conv = |v: &[u8]| u64::from_ne_bytes(v[0..8].try_into().unwrap());
-        let f32conv = |v: &[u8]| f32::from_ne_bytes(v[0..4].try_into().unwrap());
-        let f64conv = |v: &[u8]| f64::from_ne_bytes(v[0..8].try_into().unwrap());
-        let guidconv = |v: &[u8]| GuidWrap(GUID {
-            Data1: u32conv(&v[0..4]),
-            Data2: u16conv(&v[4..6]),
-            Data3: u16conv(&v[6..8]),
-            Data4: v[8..16].try_into().unwrap(),
-        });
+        // NOTE: this is expected to fail because of DeviceInterfaceDetailDataSize = 0,
+        //       but the device info data is filled anyway
+        if result != BOOL::from(FALSE) {
+            return Err(win::Error::INTERNAL_ERROR);
+        }
+        match win::Error::get() {
+            win::Error::INSUFFICIENT_BUFFER => Ok(device),
+            err => Err(err),
+        }
+    }
 
-        fn arrconv<T>(arr: &[u8], f: impl Fn(&[u8]) -> T) -> Vec<T> {
-            arr.chunks_exact(std::mem::size_of::<T>() / 8)
-                .map(f)
-                .collect()
+    /// Fetches the type and the size of the value of the given property of the device instance
+    /// that exposes this device interface, without fetching the value
+    ///
+    /// Many `DEVPKEY_Device_*` properties (e.g. `DEVPKEY_Device_InstanceId` or
+    /// `DEVPKEY_Device_Parent`) are only set on the device instance and not on its interfaces
+    pub fn fetch_device_property_info(&self, key: DEVPROPKEY) -> win::Result<Property<'_>> {
+        self.fetch_info(Some(self.fetch_device_info()?), key)
+    }
+
+    /// Fetches the value of the given property of the device instance that exposes this device interface
+    ///
+    /// See [`Self::fetch_device_property_info()`],
+    /// [`win::Error::NOT_FOUND`] is returned if the property is not set
+    pub fn fetch_device_property(&self, key: DEVPROPKEY) -> win::Result<DevProperty> {
+        self.fetch_value(Some(self.fetch_device_info()?), key)
+    }
+
+    /// Fetches the value of the given property of the container (the physical device) of the
+    /// device instance that exposes this device interface
+    ///
+    /// The `DEVPKEY_DeviceContainer_*` properties aren't served by the SetupAPI, they are read
+    /// with the device query API (`DevGetObjectProperties`) from the container whose ID is the
+    /// `DEVPKEY_Device_ContainerId` of the device instance.
+    /// [`win::Error::NOT_FOUND`] is returned if the property is not set, or if the device
+    /// has no container
+    pub fn fetch_container_property(&self, key: DEVPROPKEY) -> win::Result<DevProperty> {
+        let container = match self.fetch_device_property(DEVPKEY_Device_ContainerId)? {
+            DevProperty::Guid(v) => v,
+            _ => return Err(win::Error::INVALID_DATATYPE),
+        };
+        let id: Vec<u16> = format!("{{{container}}}")
+            .encode_utf16()
+            .chain(iter::once(0))
+            .collect();
+        let requested = DEVPROPCOMPKEY {
+            Key: key,
+            Store: DEVPROP_STORE_SYSTEM,
+            LocaleName: null(),
+        };
+        let mut count = 0;
+        let mut properties = null();
+
+        // SAFETY:
+        // https://learn.microsoft.com/en-us/windows/win32/api/devquery/nf-devquery-devgetobjectproperties#parameters
+        // `ObjectType`: is a valid `DEV_OBJECT_TYPE`
+        // `pszObjectId`: is a valid null-terminated string
+        // `QueryFlags`: no flags are valid
+        // `cRequestedProperties`: is the number of keys pointed by `pRequestedProperties`
+        // `pRequestedProperties`: points to a valid key
+        // `pcPropertyCount`: is a valid pointer to a `ULONG`
+        // `ppProperties`: is a valid pointer to a pointer
+        let result = unsafe {
+            DevGetObjectProperties(
+                DEV_OBJECT_TYPE_DEVICE_CONTAINER,
+                id.as_ptr(),
+                DEV_QUERY_FLAG_NONE,
+                1,
+                &requested,
+                &mut count,
+                &mut properties,
+            )
+        };
+        if result != S_OK {
+            return Err(hresult_error(result));
         }
+        // SAFETY: on success the function returns an array of `count` properties
+        let value = unsafe { std::slice::from_raw_parts(properties, count as usize) }
+            .iter()
+            .find(|prop| IsEqualDevPropKey(&prop.CompKey.Key, &key))
+            .filter(|prop| prop.Type != DEVPROP_TYPE_EMPTY)
+            .map(|prop| {
+                // SAFETY: `Buffer` points to `BufferSize` bytes of the value, if any
+                let raw = match prop.Buffer.is_null() {
+                    true => Vec::new(),
+                    false => unsafe {
+                        std::slice::from_raw_parts(
+                            prop.Buffer as *const u8,
+                            prop.BufferSize as usize,
+                        )
+                    }
+                    .to_vec(),
+                };
+                decode_value(prop.Type, raw, false)
+            });
+        // SAFETY: the array was allocated by `DevGetObjectProperties` and it's not used anymore
+        unsafe { DevFreeObjectProperties(count, properties) };
+        value.unwrap_or(Err(win::Error::NOT_FOUND))
+    }
 
-        use DEVPROP_TYPEMOD_ARRAY as ARR;
+    /// Fetches the value of the given `DEVPROP_TYPE_STRING` property
+    ///
+    /// [`win::Error::MORE_DATA`] is returned if the property is a string list
+    /// (see [`Self::fetch_string_list()`]), [`win::Error::INVALID_DATATYPE`] if it has another type
+    pub fn fetch_string(&self, key: DEVPROPKEY) -> win::Result<String> {
+        string_value(self.fetch_property_value(key)?)
+    }
 
-        Ok(
-            match (prop_ty & DEVPROP_MASK_TYPEMOD, prop_ty & DEVPROP_MASK_TYPE) {
-                (0, DEVPROP_TYPE_EMPTY) => P::Empty,
-                (0, DEVPROP_TYPE_NULL) => P::Null,
-                (0, DEVPROP_TYPE_BOOLEAN) => P::Bool(raw[0] as i8 == DEVPROP_TRUE),
-                (0, DEVPROP_TYPE_STRING) => P::String(
-                    // SAFETY: transmuting between plain data types doesn't cause any damage (if correctly aligned)
-                    String::from_utf16(unsafe { raw.align_to() }.1.split_last().unwrap().1)
-                        .unwrap(),
-                ),
-                (0, DEVPROP_TYPE_SBYTE) => P::I8(raw[0] as i8),
-                (0, DEVPROP_TYPE_BYTE) => P::U8(raw[0]),
-                (0, DEVPROP_TYPE_INT16) => P::I16(i16conv(&raw)),
-                (0, DEVPROP_TYPE_UINT16) => P::U16(u16conv(&raw)),
-                (0, DEVPROP_TYPE_INT32) => P::I32(i32conv(&raw)),
-                (0, DEVPROP_TYPE_UINT32) => P::U32(u32conv(&raw)),
-                (0, DEVPROP_TYPE_INT64) => P::I64(i64conv(&raw)),
-                (0, DEVPROP_TYPE_UINT64) => P::U64(u64conv(&raw)),
-                (0, DEVPROP_TYPE_FLOAT) => P::F32(f32conv(&raw)),
-                (0, DEVPROP_TYPE_DOUBLE) => P::F64(f64conv(&raw)),
-                (0, DEVPROP_TYPE_BINARY) => P::Binary(raw),
-                (0, DEVPROP_TYPE_GUID) => P::Guid(guidconv(&raw)),
-                (ARR, DEVPROP_TYPE_BOOLEAN) => {
-                    P::BoolArray(raw.into_iter().map(|v| v as i8 == DEVPROP_TRUE).collect())
-                }
-                (ARR, DEVPROP_TYPE_SBYTE) => P::I8Array(raw.into_iter().map(|v| v as i8).collect()),
-                (ARR, DEVPROP_TYPE_BYTE) => P::U8Array(raw),
-                (ARR, DEVPROP_TYPE_INT16) => P::I16Array(arrconv(&raw, i16conv)),
-                (ARR, DEVPROP_TYPE_UINT16) => P::U16Array(arrconv(&raw, u16conv)),
-                (ARR, DEVPROP_TYPE_INT32) => P::I32Array(arrconv(&raw, i32conv)),
-                (ARR, DEVPROP_TYPE_UINT32) => P::U32Array(arrconv(&raw, u32conv)),
-                (ARR, DEVPROP_TYPE_INT64) => P::I64Array(arrconv(&raw, i64conv)),
-                (ARR, DEVPROP_TYPE_UINT64) => P::U64Array(arrconv(&raw, u64conv)),
-                (ARR, DEVPROP_TYPE_FLOAT) => P::F32Array(arrconv(&raw, f32conv)),
-                (ARR, DEVPROP_TYPE_DOUBLE) => P::F64Array(arrconv(&raw, f64conv)),
-                (ARR, DEVPROP_TYPE_GUID) => P::GuidArray(arrconv(&raw, guidconv)),
-                _ => DevProperty::Unsupported(prop_ty),
-            },
-        )
+    /// Fetches the value of the given `DEVPROP_TYPE_STRING_LIST` property
+    ///
+    /// [`win::Error::INVALID_DATATYPE`] is returned if the property has a different type,
+    /// single strings included (see [`Self::fetch_string()`])
+    pub fn fetch_string_list(&self, key: DEVPROPKEY) -> win::Result<Vec<String>> {
+        typed_value(self.fetch_property_value(key)?)
     }
-}
 
-#[derive(Debug)]
-pub enum DevProperty {
-    Empty,
-    Null,
-    Bool(bool),
-    BoolArray(Vec<bool>),
-    String(String),
-    I8(i8),
-    I8Array(Vec<i8>),
-    U8(u8),
-    U8Array(Vec<u8>),
-    I16(i16),
-    I16Array(Vec<i16>),
-    U16(u16),
-    U16Array(Vec<u16>),
-    I32(i32),
-    I32Array(Vec<i32>),
-    U32(u32),
-    U32Array(Vec<u32>),
-    I64(i64),
-    I64Array(Vec<i64>),
-    U64(u64),
-    U64Array(Vec<u64>),
-    F32(f32),
-    F32Array(Vec<f32>),
-    F64(f64),
-    F64Array(Vec<f64>),
-    Binary(Vec<u8>),
-    Guid(GuidWrap),
-    GuidArray(Vec<GuidWrap>),
-    Unsupported(DEVPROPTYPE),
-}
-
-impl std::fmt::Display for DevProperty {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            DevProperty::Empty => write!(f, "#EMPTY"),
-            DevProperty::Null => write!(f, "#NULL"),
-            DevProperty::Bool(v) => write!(f, "{v}"),
-            DevProperty::BoolArray(v) => write!(f, "{v:?}"),
-            DevProperty::String(v) => write!(f, "{v}"),
-            DevProperty::I8(v) => write!(f, "{v}"),
-            DevProperty::I8Array(v) => write!(f, "{v:?}"),
-            DevProperty::U8(v) => write!(f, "{v}"),
-            DevProperty::U8Array(v) => write!(f, "{v:?}"),
-            DevProperty::I16(v) => write!(f, "{v}"),
-            DevProperty::I16Array(v) => write!(f, "{v:?}"),
-            DevProperty::U16(v) => write!(f, "{v}"),
-            DevProperty::U16Array(v) => write!(f, "{v:?}"),
-            DevProperty::I32(v) => write!(f, "{v}"),
-            DevProperty::I32Array(v) => write!(f, "{v:?}"),
-            DevProperty::U32(v) => write!(f, "{v}"),
-            DevProperty::U32Array(v) => write!(f, "{v:?}"),
-            DevProperty::I64(v) => write!(f, "{v}"),
-            DevProperty::I64Array(v) => write!(f, "{v:?}"),
-            DevProperty::U64(v) => write!(f, "{v}"),
-            DevProperty::U64Array(v) => write!(f, "{v:?}"),
-            DevProperty::F32(v) => write!(f, "{v}"),
-            DevProperty::F32Array(v) => write!(f, "{v:?}"),
-            DevProperty::F64(v) => write!(f, "{v}"),
-            DevProperty::F64Array(v) => write!(f, "{v:?}"),
-            DevProperty::Binary(v) => v.iter().try_for_each(|v| write!(f, "{v:02x}")),
-            DevProperty::Guid(v) => write!(f, "{v}"),
-            DevProperty::GuidArray(v) => write!(f, "{v:?}"),
-            DevProperty::Unsupported(v) => write!(f, "#UNSUP{{{v}}}"),
-        }
-    }
-}
-
-pub struct GuidWrap(pub GUID);
-
-impl std::fmt::Debug for GuidWrap {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("Guid")
-            .field("Data1", &self.0.Data1)
-            .field("Data2", &self.0.Data2)
-            .field("Data3", &self.0.Data3)
-            .field("Data4", &self.0.Data4)
-            .finish()
-    }
-}
-
-impl std::fmt::Display for GuidWrap {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let GUID {
-            Data1: a,
-            Data2: b,
-            Data3: c,
-            Data4: [d, e, f, g, h, i, j, k],
-        } = self.0;
-        write!(
-            fmt,
-            "{a:08x}-{b:04x}-{c:04x}-{d:02x}{e:02x}-{f:02x}{g:02x}{h:02x}{i:02x}{j:02x}{k:02x}"
-        )
+    /// Fetches the value of the given `DEVPROP_TYPE_UINT32` property
+    ///
+    /// [`win::Error::INVALID_DATATYPE`] is returned if the property has a different type
+    pub fn fetch_u32(&self, key: DEVPROPKEY) -> win::Result<u32> {
+        typed_value(self.fetch_property_value(key)?)
+    }
+
+    /// Fetches the value of the given `DEVPROP_TYPE_UINT64` property
+    ///
+    /// [`win::Error::INVALID_DATATYPE`] is returned if the property has a different type
+    pub fn fetch_u64(&self, key: DEVPROPKEY) -> win::Result<u64> {
+        typed_value(self.fetch_property_value(key)?)
+    }
+
+    /// Fetches the value of the given `DEVPROP_TYPE_BOOLEAN` property
+    ///
+    /// [`win::Error::INVALID_DATATYPE`] is returned if the property has a different type
+    pub fn fetch_bool(&self, key: DEVPROPKEY) -> win::Result<bool> {
+        typed_value(self.fetch_property_value(key)?)
+    }
+
+    /// Fetches the value of the given `DEVPROP_TYPE_GUID` property
+    ///
+    /// [`win::Error::INVALID_DATATYPE`] is returned if the property has a different type
+    pub fn fetch_guid(&self, key: DEVPROPKEY) -> win::Result<GUID> {
+        typed_value(self.fetch_property_value(key)?)
+    }
+
+    /// Returns the problem of the device instance that exposes this device interface
+    ///
+    /// `None` is returned when the device has no problem, that is when the property is not set
+    /// or is 0
+    pub fn fetch_problem_code(&self) -> win::Result<Option<devprop::ProblemCode>> {
+        match self.fetch_device_property(DEVPKEY_Device_ProblemCode) {
+            Ok(DevProperty::U32(0)) | Err(win::Error::NOT_FOUND) => Ok(None),
+            Ok(DevProperty::U32(code)) => Ok(Some(devprop::ProblemCode::from_code(code))),
+            Ok(_) => Err(win::Error::INVALID_DATATYPE),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Fetches the value of the given property from the given store
+    fn fetch_from(&self, store: PropertyStore, key: DEVPROPKEY) -> win::Result<DevProperty> {
+        match store {
+            PropertyStore::Interface => self.fetch_property_value(key),
+            PropertyStore::Device => self.fetch_device_property(key),
+            PropertyStore::Container => self.fetch_container_property(key),
+        }
+    }
+
+    /// Returns whether or not the device needs a reboot to become fully functional
+    ///
+    /// The property of the device instance is checked first, falling back to the one of its
+    /// container (see [`Self::fetch_container_property()`]),
+    /// `None` is returned when neither of them is set
+    pub fn is_reboot_required(&self) -> win::Result<Option<bool>> {
+        let lookups = [
+            (PropertyStore::Device, DEVPKEY_Device_IsRebootRequired),
+            (
+                PropertyStore::Container,
+                DEVPKEY_DeviceContainer_IsRebootRequired,
+            ),
+        ];
+        first_of(
+            lookups.map(|(store, key)| move || self.fetch_from(store, key)),
+            bool_value,
+        )
+    }
+
+    /// Returns the friendly name of the physical device container
+    ///
+    /// This is the name shown by Windows for the whole device (e.g. "SanDisk Ultra USB Device"),
+    /// `None` is returned when it's not set, see [`Self::fetch_container_property()`]
+    pub fn container_friendly_name(&self) -> win::Result<Option<String>> {
+        first_of(
+            [|| self.fetch_container_property(DEVPKEY_DeviceContainer_FriendlyName)],
+            non_empty_string,
+        )
+    }
+
+    /// Returns the best human-readable name of the device interface, for listing it to the user
+    ///
+    /// The first string that is set among the friendly name of the container
+    /// (`DEVPKEY_DeviceContainer_FriendlyName`), the friendly name of the interface
+    /// (`DEVPKEY_DeviceInterface_FriendlyName`), the friendly name of its device
+    /// (`DEVPKEY_Device_FriendlyName`) and the description of its device (`DEVPKEY_Device_DeviceDesc`)
+    /// is returned, falling back to the path (converted lossily)
+    pub fn fetch_friendly_name(&self) -> win::Result<String> {
+        let name = first_of(
+            FRIENDLY_NAME_CHAIN.map(|(store, key)| move || self.fetch_from(store, key)),
+            non_empty_string,
+        )?;
+        match name {
+            Some(name) => Ok(name),
+            None => Ok(String::from_utf16_lossy(&self.fetch_path_wide()?)),
+        }
+    }
+
+    /// Returns the instance ID of the device that exposes this device interface
+    ///
+    /// Unlike the path, this identifier (e.g. `USBSTOR\\DISK&VEN_...\\...`) is stable across reboots.
+    /// An error is returned if the interface has no associated device instance
+    /// or if the property is not a string
+    pub fn fetch_instance_id(&self) -> win::Result<String> {
+        match self.fetch_device_property(DEVPKEY_Device_InstanceId)? {
+            DevProperty::String(id) => Ok(id),
+            _ => Err(win::Error::INVALID_DATATYPE),
+        }
+    }
+
+    /// Fetches the values of the given properties, in the same order
+    ///
+    /// Failing to fetch the value of a single property doesn't abort the others,
+    /// its error is returned in place of the value.
+    /// The whole batch fails only if the result can't be allocated, or if the device interface
+    /// has been removed ([`win::Error::NO_SUCH_DEVICE_INTERFACE`]), as all the keys would fail
+    pub fn fetch_properties(
+        &self,
+        keys: &[DEVPROPKEY],
+    ) -> win::Result<Vec<(DEVPROPKEY, win::Result<DevProperty>)>> {
+        let mut properties = Vec::new();
+        properties
+            .try_reserve_exact(keys.len())
+            .map_err(|_| win::Error::NOT_ENOUGH_MEMORY)?;
+        for &key in keys {
+            match self.fetch_property_value(key) {
+                Err(win::Error::NO_SUCH_DEVICE_INTERFACE) => {
+                    return Err(win::Error::NO_SUCH_DEVICE_INTERFACE)
+                }
+                value => properties.push((key, value)),
+            }
+        }
+        Ok(properties)
+    }
+
+    /// Fetches all the properties of the device interface, mapped by their key
+    ///
+    /// Failing to fetch the value of a single property doesn't abort the others,
+    /// its error is stored in place of the value
+    pub fn fetch_all_properties(
+        &self,
+    ) -> win::Result<HashMap<DevPropKey, win::Result<DevProperty>>> {
+        Ok(self
+            .fetch_property_keys()?
+            .into_iter()
+            .map(|key| (DevPropKey(key), self.fetch_property_value(key)))
+            .collect())
+    }
+
+    /// Returns an iterator over the properties of the device interface, in the order they are listed
+    ///
+    /// The keys are fetched first, while each value is fetched and decoded only when it's reached.
+    /// If the keys can't be fetched their error is the only item
+    pub fn properties(&self) -> impl Iterator<Item = win::Result<(DEVPROPKEY, DevProperty)>> + '_ {
+        let (keys, err) = match self.fetch_property_keys() {
+            Ok(keys) => (keys, None),
+            Err(err) => (Vec::new(), Some(err)),
+        };
+        err.map(Err).into_iter().chain(
+            keys.into_iter()
+                .map(move |key| self.fetch_property_value(key).map(|value| (key, value))),
+        )
+    }
+
+    /// Returns an iterator over the properties of the device interface whose base type is in the mask,
+    /// in the order they are listed
+    ///
+    /// Like [`Self::properties()`], but the type of each property is checked before fetching
+    /// its value, and the properties of the other types are filtered out entirely,
+    /// so their values are never fetched nor decoded (e.g. large binary blobs).
+    /// The modifiers are ignored, so a mask with `DEVPROP_TYPE_STRING` selects string lists too.
+    pub fn properties_of_types(
+        &self,
+        mask: TypeMask,
+    ) -> impl Iterator<Item = win::Result<(DEVPROPKEY, DevProperty)>> + '_ {
+        let (keys, err) = match self.fetch_property_keys() {
+            Ok(keys) => (keys, None),
+            Err(err) => (Vec::new(), Some(err)),
+        };
+        err.map(Err)
+            .into_iter()
+            .chain(keys.into_iter().filter_map(move |key| {
+                let info = match self.fetch_property_info(key) {
+                    Ok(info) => info,
+                    Err(err) => return Some(Err(err)),
+                };
+                mask.contains(info.ty())
+                    .then(|| info.fetch_value().map(|value| (key, value)))
+            }))
+    }
+
+    /// Fetches all the data of the device interface into a value that doesn't borrow the set
+    ///
+    /// Unlike `self`, the returned value is [`Send`], so it can be handed to other threads.
+    /// Like in [`Self::fetch_all_properties()`], failing to fetch the value of a single property
+    /// doesn't lose the whole device, its error is stored in place of the value,
+    /// while failing to list them is an error
+    pub fn fetch_owned(&self) -> win::Result<OwnedDevInterface> {
+        let properties = self
+            .fetch_property_keys()?
+            .into_iter()
+            .map(|key| (key, self.fetch_property_value(key)))
+            .collect();
+        Ok(OwnedDevInterface {
+            class: self.data.InterfaceClassGuid,
+            flags: self.data.Flags,
+            path: self.fetch_path()?,
+            properties,
+        })
+    }
+
+    /// Same as [`Self::fetch_owned()`], returning the value by its [`OwnedDeviceInfo`] name
+    pub fn to_owned_info(&self) -> win::Result<OwnedDeviceInfo> {
+        self.fetch_owned()
+    }
+
+    /// Fetches all the data of the device interface into a [`DeviceSnapshot`]
+    pub fn snapshot(&self) -> win::Result<DeviceSnapshot> {
+        self.fetch_owned().map(DeviceSnapshot::from)
+    }
+}
+
+/// Formats the interface class and the raw flags of the device interface
+///
+/// No WinAPI call is made, the path must be fetched separately with [`DevInterfaceData::fetch_path()`]
+impl std::fmt::Debug for DevInterfaceData<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DevInterfaceData")
+            .field("class", &GuidWrap(self.data.InterfaceClassGuid))
+            .field("active", &self.is_active())
+            .field("default", &self.is_default())
+            .field("removed", &self.is_removed())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Formats the status of the device interface followed by its path
+///
+/// The status is made of three characters: `!` if it's removed, `#` if it's the default
+/// for its class and `+` if it's active (`-` otherwise), e.g. ` #+\\?\usbstor#disk...`
+///
+/// The path is fetched and converted lossily to UTF-8, if it can't be fetched the error is shown instead
+impl std::fmt::Display for DevInterfaceData<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let removed = if self.is_removed() { '!' } else { ' ' };
+        let default = if self.is_default() { '#' } else { ' ' };
+        let active = if self.is_active() { '+' } else { '-' };
+        write!(f, "{removed}{default}{active}")?;
+        match self.fetch_path_wide() {
+            Ok(path) => write!(f, "{}", String::from_utf16_lossy(&path)),
+            Err(err) => write!(f, "<{err}>"),
+        }
+    }
+}
+
+/// The `SPINT_*` status flags of a device interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct InterfaceFlags(pub DWORD);
+
+impl InterfaceFlags {
+    /// `SPINT_ACTIVE`: the interface is active (enabled)
+    pub const ACTIVE: Self = Self(SPINT_ACTIVE);
+    /// `SPINT_DEFAULT`: the interface is the default one for its class
+    pub const DEFAULT: Self = Self(SPINT_DEFAULT);
+    /// `SPINT_REMOVED`: the interface is removed
+    pub const REMOVED: Self = Self(SPINT_REMOVED);
+
+    /// Returns whether or not all the flags set in `other` are set in `self` too
+    pub fn contains(self, other: Self) -> bool {
+        (self.0 & other.0) == other.0
+    }
+
+    /// Returns whether or not [`Self::ACTIVE`] is set
+    pub fn is_active(self) -> bool {
+        self.contains(Self::ACTIVE)
+    }
+
+    /// Returns whether or not [`Self::DEFAULT`] is set
+    pub fn is_default(self) -> bool {
+        self.contains(Self::DEFAULT)
+    }
+
+    /// Returns whether or not [`Self::REMOVED`] is set
+    pub fn is_removed(self) -> bool {
+        self.contains(Self::REMOVED)
+    }
+}
+
+impl std::ops::BitOr for InterfaceFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// The components of the path of a device interface, returned by [`DevInterfaceData::parse_path()`]
+///
+/// A path has the form `\\?\<instance>#{<class>}[\<reference>]`, e.g.
+/// `\\?\usbstor#disk&ven_x&prod_y#0001&0#{53f56307-b6bf-11d0-94f2-00a0c91efb8b}`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedPath {
+    /// The part identifying the device instance, as it appears in the path
+    ///
+    /// It's the instance ID of the device with the `\` replaced by `#`
+    pub instance: String,
+    /// The device interface class embedded in the path
+    pub class: GuidWrap,
+    /// The reference string that follows the class, if any
+    pub reference: Option<String>,
+}
+
+impl ParsedPath {
+    /// The prefix of all the device interface paths
+    const PREFIX: &'static str = "\\\\?\\";
+
+    /// Splits the given device interface path into its components
+    ///
+    /// Returns [`win::Error::INVALID_DATA`] if the path doesn't have the expected form
+    pub fn parse(path: &str) -> win::Result<Self> {
+        let path = path
+            .strip_prefix(Self::PREFIX)
+            .ok_or(win::Error::INVALID_DATA)?;
+        // NOTE: neither the instance nor the class contain a `\`, the reference string might
+        let (body, reference) = match path.split_once('\\') {
+            Some((body, reference)) => (body, Some(reference)),
+            None => (path, None),
+        };
+        let (instance, class) = body.rsplit_once('#').ok_or(win::Error::INVALID_DATA)?;
+        if instance.is_empty() || !class.starts_with('{') {
+            return Err(win::Error::INVALID_DATA);
+        }
+        let class = class.parse().map_err(|_| win::Error::INVALID_DATA)?;
+        Ok(Self {
+            instance: instance.to_string(),
+            class,
+            reference: reference.filter(|v| !v.is_empty()).map(str::to_string),
+        })
+    }
+}
+
+/// The type and the size of the value of a property of a device interface
+///
+/// This is returned by [`DevInterfaceData::fetch_property_info()`] and allows
+/// to inspect the value before fetching it
+pub struct Property<'a> {
+    /// The device interface the property belongs to
+    data: &'a DevInterfaceData<'a>,
+    /// The device instance the property belongs to, `None` if it belongs to the interface
+    device: Option<SP_DEVINFO_DATA>,
+    /// The key of the property
+    key: DEVPROPKEY,
+    /// The type of the value of the property
+    ty: DEVPROPTYPE,
+    /// The size in bytes of the value of the property
+    size: DWORD,
+}
+
+impl Property<'_> {
+    /// Returns the key of the property
+    pub fn key(&self) -> DEVPROPKEY {
+        self.key
+    }
+
+    /// Returns the `DEVPROP_TYPE_*` type of the value, modifiers included
+    pub fn ty(&self) -> DEVPROPTYPE {
+        self.ty
+    }
+
+    /// Returns the size in bytes of the value
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Returns a readable name of the type of the value, see [`devprop::type_name()`]
+    pub fn type_name(&self) -> &'static str {
+        devprop::type_name(self.ty)
+    }
+
+    /// Fetches the value and decodes it as a `T`
+    ///
+    /// Unlike decoding the raw bytes by hand, this checks that the type of the property is
+    /// the one of `T`, returning [`win::Error::INVALID_DATATYPE`] if it's not, and that the size of
+    /// the value is correct, returning [`win::Error::INVALID_DATA`] if it's not.
+    pub fn try_fetch<T: DevPropTypeTag>(&self) -> win::Result<T> {
+        if self.ty != T::TYPE {
+            return Err(win::Error::INVALID_DATATYPE);
+        }
+        T::decode(&self.fetch_raw()?).ok_or(win::Error::INVALID_DATA)
+    }
+
+    /// Fetches the raw bytes of the value
+    pub fn fetch_raw(&self) -> win::Result<Vec<u8>> {
+        let mut raw = Vec::new();
+        self.fetch_into(&mut raw)?;
+        Ok(raw)
+    }
+
+    /// Fetches the raw bytes of the value into the given buffer, replacing its content
+    ///
+    /// The buffer is resized to the size of the value, reusing its allocation when possible,
+    /// so the same buffer can be used to read many values without allocating each time.
+    /// [`win::Error::INVALID_DATATYPE`] is returned if the type of the value changed
+    /// since this info was fetched.
+    pub fn fetch_into(&self, buf: &mut Vec<u8>) -> win::Result<()> {
+        buf.clear();
+        // NOTE: there's nothing to fetch, and the WinAPI doesn't accept an empty buffer that's not null
+        if self.size == 0 {
+            return Ok(());
+        }
+        buf.try_reserve_exact(self.size as usize)
+            .map_err(|_| win::Error::NOT_ENOUGH_MEMORY)?;
+        buf.resize(self.size as usize, 0);
+        let mut ty = 0;
+
+        // SAFETY:
+        // https://docs.microsoft.com/en-us/windows/win32/api/setupapi/nf-setupapi-setupdigetdeviceinterfacepropertyw#parameters
+        // https://docs.microsoft.com/en-us/windows/win32/api/setupapi/nf-setupapi-setupdigetdevicepropertyw#parameters
+        // `DeviceInfoSet`: is a valid handle because of the invariants of DevInterfaceData
+        // `DeviceInterfaceData`: is correctly initialized because of the invariants of DevInterfaceData
+        // `DeviceInfoData`: is the one used to fetch this property info
+        // `PropertyKey`: any value is allowed (if the property is wrong an error is returned)
+        // `PropertyType`: a valid pointer to a `DEVPROPTYPE`
+        // `PropertyBuffer`: is a valid buffer of `PropertyBufferSize` bytes (resized just above)
+        // `PropertyBufferSize`: is the size returned by the previous call
+        // `RequiredSize`: can be null
+        // `Flags`: must be 0
+        let result = unsafe {
+            self.data.get_property(
+                self.device,
+                &self.key,
+                &mut ty,
+                buf.as_mut_ptr(),
+                self.size,
+                null_mut(),
+            )
+        };
+        if result != BOOL::from(TRUE) {
+            buf.clear();
+            return Err(win::Error::get());
+        }
+        // NOTE: the value may have been replaced by one of a different type in the meantime
+        if ty != self.ty {
+            buf.clear();
+            return Err(win::Error::INVALID_DATATYPE);
+        }
+        Ok(())
+    }
+
+    /// Fetches the UTF-16 code units of a `DEVPROP_TYPE_STRING` (or `DEVPROP_TYPE_STRING_INDIRECT`)
+    /// value, without the null-terminator
+    ///
+    /// Unlike [`Self::fetch_value()`] the value is fetched straight into the returned buffer
+    /// and not converted to UTF-8, so it can be decoded lazily or handed back to the WinAPI.
+    /// [`win::Error::INVALID_DATATYPE`] is returned if the property has a different type.
+    pub fn fetch_string_buf(&self) -> win::Result<Box<[u16]>> {
+        if !matches!(self.ty, DEVPROP_TYPE_STRING | DEVPROP_TYPE_STRING_INDIRECT) {
+            return Err(win::Error::INVALID_DATATYPE);
+        }
+        if !self.size.is_multiple_of(2) {
+            return Err(win::Error::INVALID_DATA);
+        }
+        // NOTE: there's nothing to fetch, and the WinAPI doesn't accept an empty buffer that's not null
+        if self.size == 0 {
+            return Ok(Box::new([]));
+        }
+        let mut ty = 0;
+        let mut buf = try_alloc(self.size as usize / 2, 0u16)?;
+
+        // SAFETY:
+        // https://docs.microsoft.com/en-us/windows/win32/api/setupapi/nf-setupapi-setupdigetdeviceinterfacepropertyw#parameters
+        // https://docs.microsoft.com/en-us/windows/win32/api/setupapi/nf-setupapi-setupdigetdevicepropertyw#parameters
+        // `DeviceInfoSet`: is a valid handle because of the invariants of DevInterfaceData
+        // `DeviceInterfaceData`: is correctly initialized because of the invariants of DevInterfaceData
+        // `DeviceInfoData`: is the one used to fetch this property info
+        // `PropertyKey`: any value is allowed (if the property is wrong an error is returned)
+        // `PropertyType`: a valid pointer to a `DEVPROPTYPE`
+        // `PropertyBuffer`: is a valid buffer of `PropertyBufferSize` bytes (half as many `u16`)
+        // `PropertyBufferSize`: is the size returned by the previous call
+        // `RequiredSize`: can be null
+        // `Flags`: must be 0
+        let result = unsafe {
+            self.data.get_property(
+                self.device,
+                &self.key,
+                &mut ty,
+                buf.as_mut_ptr().cast(),
+                self.size,
+                null_mut(),
+            )
+        };
+        if result != BOOL::from(TRUE) {
+            return Err(win::Error::get());
+        }
+        if buf.last() == Some(&0) {
+            buf.pop();
+        }
+        Ok(buf.into_boxed_slice())
+    }
+
+    /// Fetches the value and decodes it according to its type
+    ///
+    /// Strings that aren't valid UTF-16 make this fail with [`win::Error::INVALID_DATA`],
+    /// see [`Self::fetch_value_lossy()`] to decode them anyway
+    pub fn fetch_value(&self) -> win::Result<DevProperty> {
+        decode_value(self.ty, self.fetch_raw()?, false)
+    }
+
+    /// Same as [`Self::fetch_value()`] but the invalid UTF-16 sequences of strings
+    /// are replaced with U+FFFD instead of making it fail
+    pub fn fetch_value_lossy(&self) -> win::Result<DevProperty> {
+        decode_value(self.ty, self.fetch_raw()?, true)
+    }
+}
+
+/// Decodes the raw bytes of a value of the given `DEVPROP_TYPE_*` type
+///
+/// When `lossy` is set the invalid UTF-16 sequences of strings are replaced with U+FFFD,
+/// otherwise they make it fail
+fn decode_value(prop_ty: DEVPROPTYPE, raw: Vec<u8>, lossy: bool) -> win::Result<DevProperty> {
+    use DevProperty as P;
+
+    let i16conv = |v: &[u8]| i16::from_ne_bytes([v[0], v[1]]);
+    let u16conv = |v: &[u8]| u16::from_ne_bytes([v[0], v[1]]);
+    let i32conv = |v: &[u8]| i32::from_ne_bytes(v[0..4].try_into().unwrap());
+    let u32conv = |v: &[u8]| u32::from_ne_bytes(v[0..4].try_into().unwrap());
+    let i64conv = |v: &[u8]| i64::from_ne_bytes(v[0..8].try_into().unwrap());
+    let u64conv = |v: &[u8]| u64::from_ne_bytes(v[0..8].try_into().unwrap());
+    let f32conv = |v: &[u8]| f32::from_ne_bytes(v[0..4].try_into().unwrap());
+    let f64conv = |v: &[u8]| f64::from_ne_bytes(v[0..8].try_into().unwrap());
+    let guidconv = |v: &[u8]| {
+        GuidWrap(GUID {
+            Data1: u32conv(&v[0..4]),
+            Data2: u16conv(&v[4..6]),
+            Data3: u16conv(&v[6..8]),
+            Data4: v[8..16].try_into().unwrap(),
+        })
+    };
+
+    let keyconv = |v: &[u8]| {
+        DevPropKey(DEVPROPKEY {
+            fmtid: guidconv(&v[0..16]).0,
+            pid: u32conv(&v[16..20]),
+        })
+    };
+
+    // NOTE: the buffer is not guaranteed to be aligned for `u16`, so the code units are
+    //       built from each pair of bytes, and an odd length would drop the last byte
+    let utf16conv = |v: &[u8]| -> win::Result<Vec<u16>> {
+        if !v.len().is_multiple_of(2) {
+            return Err(win::Error::INVALID_DATA);
+        }
+        Ok(v.chunks_exact(2).map(u16conv).collect())
+    };
+
+    let wideconv = |v: &[u16]| -> win::Result<String> {
+        match lossy {
+            true => Ok(String::from_utf16_lossy(v)),
+            false => String::from_utf16(v).map_err(|_| win::Error::INVALID_DATA),
+        }
+    };
+
+    let strconv = |v: &[u8]| -> win::Result<String> {
+        let utf16 = utf16conv(v)?;
+        // NOTE: empty strings may be returned without the null-terminator
+        wideconv(utf16.strip_suffix(&[0]).unwrap_or(&utf16))
+    };
+
+    // NOTE: every element type has the same size of its raw representation,
+    //       and the length of the array has been checked to be a multiple of it
+    fn arrconv<T>(arr: &[u8], f: impl Fn(&[u8]) -> T) -> Vec<T> {
+        arr.chunks_exact(size_of::<T>()).map(f).collect()
+    }
+
+    use DEVPROP_TYPEMOD_ARRAY as ARR;
+    use DEVPROP_TYPEMOD_LIST as LIST;
+
+    // NOTE: a fixed size value with a different size would be decoded wrongly (or panic)
+    let fixed_size = match prop_ty & DEVPROP_MASK_TYPE {
+        DEVPROP_TYPE_BOOLEAN | DEVPROP_TYPE_SBYTE | DEVPROP_TYPE_BYTE => Some(1),
+        DEVPROP_TYPE_INT16 | DEVPROP_TYPE_UINT16 => Some(2),
+        DEVPROP_TYPE_INT32 | DEVPROP_TYPE_UINT32 | DEVPROP_TYPE_FLOAT => Some(4),
+        DEVPROP_TYPE_DEVPROPTYPE => Some(size_of::<DEVPROPTYPE>()),
+        DEVPROP_TYPE_INT64 | DEVPROP_TYPE_UINT64 | DEVPROP_TYPE_DOUBLE => Some(8),
+        DEVPROP_TYPE_FILETIME | DEVPROP_TYPE_CURRENCY | DEVPROP_TYPE_DATE => Some(8),
+        DEVPROP_TYPE_DECIMAL => Some(16),
+        DEVPROP_TYPE_GUID => Some(size_of::<GUID>()),
+        DEVPROP_TYPE_DEVPROPKEY => Some(size_of::<DEVPROPKEY>()),
+        _ => None,
+    };
+    if prop_ty & DEVPROP_MASK_TYPEMOD == 0 && fixed_size.is_some_and(|size| size != raw.len()) {
+        // NOTE: some drivers set fixed size values without any data
+        if raw.is_empty() {
+            return Ok(P::Empty);
+        }
+        return Err(win::Error::INVALID_DATA);
+    }
+    // NOTE: an array with a trailing partial element would otherwise be silently truncated
+    if prop_ty & DEVPROP_MASK_TYPEMOD == ARR
+        && fixed_size.is_some_and(|size| !raw.len().is_multiple_of(size))
+    {
+        return Err(win::Error::INVALID_DATA);
+    }
+
+    Ok(
+        match (prop_ty & DEVPROP_MASK_TYPEMOD, prop_ty & DEVPROP_MASK_TYPE) {
+            (0, DEVPROP_TYPE_EMPTY) => P::Empty,
+            (0, DEVPROP_TYPE_NULL) => P::Null,
+            (0, DEVPROP_TYPE_BOOLEAN) => P::Bool(raw[0] as i8 == DEVPROP_TRUE),
+            (0, DEVPROP_TYPE_STRING) => P::String(strconv(&raw)?),
+            (0, DEVPROP_TYPE_STRING_INDIRECT) => P::IndirectString(strconv(&raw)?),
+            (0, DEVPROP_TYPE_SBYTE) => P::I8(raw[0] as i8),
+            (0, DEVPROP_TYPE_BYTE) => P::U8(raw[0]),
+            (0, DEVPROP_TYPE_INT16) => P::I16(i16conv(&raw)),
+            (0, DEVPROP_TYPE_UINT16) => P::U16(u16conv(&raw)),
+            (0, DEVPROP_TYPE_INT32) => P::I32(i32conv(&raw)),
+            (0, DEVPROP_TYPE_UINT32) => P::U32(u32conv(&raw)),
+            (0, DEVPROP_TYPE_INT64) => P::I64(i64conv(&raw)),
+            (0, DEVPROP_TYPE_UINT64) => P::U64(u64conv(&raw)),
+            (0, DEVPROP_TYPE_FLOAT) => P::F32(f32conv(&raw)),
+            (0, DEVPROP_TYPE_DOUBLE) => P::F64(f64conv(&raw)),
+            (0, DEVPROP_TYPE_GUID) => P::Guid(guidconv(&raw)),
+            (0, DEVPROP_TYPE_FILETIME) => P::FileTime(u64conv(&raw)),
+            (0, DEVPROP_TYPE_DECIMAL) => P::Decimal(raw[..].try_into().unwrap()),
+            (0, DEVPROP_TYPE_CURRENCY) => P::Currency(i64conv(&raw)),
+            (0, DEVPROP_TYPE_DATE) => P::Date(f64conv(&raw)),
+            (0, DEVPROP_TYPE_SECURITY_DESCRIPTOR) => P::SecurityDescriptor(raw),
+            (0, DEVPROP_TYPE_DEVPROPKEY) => P::DevPropKey(keyconv(&raw)),
+            (0, DEVPROP_TYPE_DEVPROPTYPE) => P::PropType(u32conv(&raw)),
+            (ARR, DEVPROP_TYPE_BOOLEAN) => {
+                P::BoolArray(raw.into_iter().map(|v| v as i8 == DEVPROP_TRUE).collect())
+            }
+            (ARR, DEVPROP_TYPE_SBYTE) => P::I8Array(raw.into_iter().map(|v| v as i8).collect()),
+            // NOTE: this is `DEVPROP_TYPE_BINARY`
+            (ARR, DEVPROP_TYPE_BYTE) => P::Binary(raw),
+            (ARR, DEVPROP_TYPE_INT16) => P::I16Array(arrconv(&raw, i16conv)),
+            (ARR, DEVPROP_TYPE_UINT16) => P::U16Array(arrconv(&raw, u16conv)),
+            (ARR, DEVPROP_TYPE_INT32) => P::I32Array(arrconv(&raw, i32conv)),
+            (ARR, DEVPROP_TYPE_UINT32) => P::U32Array(arrconv(&raw, u32conv)),
+            (ARR, DEVPROP_TYPE_INT64) => P::I64Array(arrconv(&raw, i64conv)),
+            (ARR, DEVPROP_TYPE_UINT64) => P::U64Array(arrconv(&raw, u64conv)),
+            (ARR, DEVPROP_TYPE_FLOAT) => P::F32Array(arrconv(&raw, f32conv)),
+            (ARR, DEVPROP_TYPE_DOUBLE) => P::F64Array(arrconv(&raw, f64conv)),
+            (ARR, DEVPROP_TYPE_GUID) => P::GuidArray(arrconv(&raw, guidconv)),
+            (ARR, DEVPROP_TYPE_DEVPROPKEY) => P::DevPropKeyArray(
+                raw.chunks_exact(size_of::<DEVPROPKEY>())
+                    .map(keyconv)
+                    .collect(),
+            ),
+            (LIST, DEVPROP_TYPE_STRING) => P::StringList(
+                utf16conv(&raw)?
+                    // NOTE: the strings are separated by a null and the list is terminated by an empty one
+                    .split(|&c| c == 0)
+                    .take_while(|s| !s.is_empty())
+                    .map(wideconv)
+                    .collect::<win::Result<_>>()?,
+            ),
+            _ => DevProperty::Unsupported {
+                ty: prop_ty,
+                data: raw.into_boxed_slice(),
+            },
+        },
+    )
+}
+
+/// The size of the buffer used to fetch a value before knowing its actual size
+const OPTIMISTIC_BUFFER_SIZE: usize = 256;
+
+/// Allocates a vector of `len` copies of `value`
+///
+/// Unlike `vec![value; len]` this doesn't abort when the allocation fails
+/// (e.g. because of an absurd size reported by the WinAPI), [`win::Error::NOT_ENOUGH_MEMORY`]
+/// is returned instead
+fn try_alloc<T: Clone>(len: usize, value: T) -> win::Result<Vec<T>> {
+    let mut vec = Vec::new();
+    vec.try_reserve_exact(len)
+        .map_err(|_| win::Error::NOT_ENOUGH_MEMORY)?;
+    vec.resize(len, value);
+    Ok(vec)
+}
+
+/// Allocates a zeroed buffer of at least `size` bytes, aligned for `T`
+///
+/// Use this instead of [`try_alloc()`] when the WinAPI fills a buffer that starts with a `T`
+/// but may not write all of it, like `SP_DEVICE_INTERFACE_DETAIL_DATA_W`,
+/// buffers that are only read as bytes after being fully overwritten don't need it
+///
+/// # Safety
+///
+/// `T` must be valid when zero initialized
+unsafe fn try_alloc_zeroed<T: Clone>(size: usize) -> win::Result<Vec<T>> {
+    // NOTE: at least one element, to always have a valid `T` at the start of the buffer
+    try_alloc(size.div_ceil(size_of::<T>()).max(1), zeroed())
+}
+
+/// Returns whether or not the two paths are equal, ignoring the case of the letters
+///
+/// The comparison is the ordinal ignore-case one used by Windows for the paths:
+/// the UTF-16 code units are compared after being uppercased one by one,
+/// without any linguistic rule (e.g. `ß` doesn't match `SS`)
+pub fn paths_eq_ignore_case(a: &OsStr, b: &OsStr) -> win::Result<bool> {
+    wide_eq_ignore_case(
+        &a.encode_wide().collect::<Vec<_>>(),
+        &b.encode_wide().collect::<Vec<_>>(),
+    )
+}
+
+/// The value returned by `CompareStringOrdinal` when the strings are equal
+/// (not defined by `winapi`)
+const CSTR_EQUAL: i32 = 2;
+
+/// Same as [`paths_eq_ignore_case()`] but on the UTF-16 code units of the paths
+fn wide_eq_ignore_case(a: &[u16], b: &[u16]) -> win::Result<bool> {
+    // NOTE: strings with a different length can't be equal, as the comparison is per code unit
+    if a.len() != b.len() {
+        return Ok(false);
+    }
+    let len = a.len().try_into().map_err(|_| win::Error::INVALID_DATA)?;
+    // SAFETY:
+    // https://docs.microsoft.com/en-us/windows/win32/api/stringapiset/nf-stringapiset-comparestringordinal#parameters
+    // `lpString1` and `lpString2`: are valid for the given number of code units
+    // `cchCount1` and `cchCount2`: are the lengths of the strings, which aren't null-terminated
+    // `bIgnoreCase`: TRUE to ignore the case
+    let result =
+        unsafe { CompareStringOrdinal(a.as_ptr(), len, b.as_ptr(), len, BOOL::from(TRUE)) };
+    match result {
+        0 => Err(win::Error::get()),
+        result => Ok(result == CSTR_EQUAL),
+    }
+}
+
+/// The well-known device interface classes with their names
+static INTERFACE_CLASSES: [(&str, GUID); 18] = with_name!([
+    GUID_DEVINTERFACE_DISK,
+    GUID_DEVINTERFACE_CDROM,
+    GUID_DEVINTERFACE_PARTITION,
+    GUID_DEVINTERFACE_TAPE,
+    GUID_DEVINTERFACE_WRITEONCEDISK,
+    GUID_DEVINTERFACE_VOLUME,
+    GUID_DEVINTERFACE_MEDIUMCHANGER,
+    GUID_DEVINTERFACE_FLOPPY,
+    GUID_DEVINTERFACE_CDCHANGER,
+    GUID_DEVINTERFACE_STORAGEPORT,
+    GUID_DEVINTERFACE_VMLUN,
+    GUID_DEVINTERFACE_SES,
+    GUID_DEVINTERFACE_SERVICE_VOLUME,
+    GUID_DEVINTERFACE_HIDDEN_VOLUME,
+    GUID_DEVINTERFACE_UNIFIED_ACCESS_RPMB,
+    GUID_DEVINTERFACE_SCM_PHYSICAL_DEVICE,
+    GUID_DEVINTERFACE_COMPORT,
+    GUID_DEVINTERFACE_SERENUM_BUS_ENUMERATOR,
+]);
+
+/// Returns the well-known device interface classes this crate knows about,
+/// each with the name of the constant defining it (e.g. `GUID_DEVINTERFACE_DISK`)
+///
+/// Most of them are storage classes, the others are the ones of the serial ports
+/// (`GUID_DEVINTERFACE_COMPORT` and `GUID_DEVINTERFACE_SERENUM_BUS_ENUMERATOR`),
+/// which are defined alongside them in `winioctl.h`
+///
+/// These are the classes enumerated by [`DevInterfaceSet::enumerate_known()`]
+pub fn storage_interface_guids() -> &'static [(&'static str, GUID)] {
+    &INTERFACE_CLASSES
+}
+
+/// Returns the name of the constant defining the given device interface class GUID
+/// (e.g. `GUID_DEVINTERFACE_DISK`)
+///
+/// `None` is returned for classes that aren't well-known
+pub fn interface_class_name(guid: &GUID) -> Option<&'static str> {
+    INTERFACE_CLASSES
+        .iter()
+        .find_map(|(name, g)| IsEqualGUID(g, guid).then_some(*name))
+}
+
+/// Returns the device interface class GUID defined by the constant with the given name
+/// (e.g. `GUID_DEVINTERFACE_DISK`)
+///
+/// `None` is returned for classes that aren't well-known
+pub fn interface_class_guid(name: &str) -> Option<GUID> {
+    INTERFACE_CLASSES
+        .iter()
+        .find_map(|(n, guid)| (*n == name).then_some(*guid))
+}
+
+/// Returns the value of a `String`, used by [`DevInterfaceData::fetch_string()`]
+///
+/// A `StringList` is reported with [`win::Error::MORE_DATA`], to tell it apart from the other
+/// variants ([`win::Error::INVALID_DATATYPE`])
+fn string_value(value: DevProperty) -> win::Result<String> {
+    match value {
+        DevProperty::String(v) => Ok(v),
+        DevProperty::StringList(_) => Err(win::Error::MORE_DATA),
+        _ => Err(win::Error::INVALID_DATATYPE),
+    }
+}
+
+/// Converts the value into the type of its variant, used by the typed `fetch_*` methods of
+/// [`DevInterfaceData`], [`win::Error::INVALID_DATATYPE`] is returned if it doesn't match
+fn typed_value<T>(value: DevProperty) -> win::Result<T>
+where
+    T: TryFrom<DevProperty, Error = DevPropertyConversionError>,
+{
+    T::try_from(value).map_err(|_| win::Error::INVALID_DATATYPE)
+}
+
+/// Where the value of a property is fetched from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PropertyStore {
+    /// The device interface itself, see [`DevInterfaceData::fetch_property_value()`]
+    Interface,
+    /// The device instance, see [`DevInterfaceData::fetch_device_property()`]
+    Device,
+    /// The container of the device instance, see [`DevInterfaceData::fetch_container_property()`]
+    Container,
+}
+
+/// The properties [`DevInterfaceData::fetch_friendly_name()`] looks for, in order
+const FRIENDLY_NAME_CHAIN: [(PropertyStore, DEVPROPKEY); 4] = [
+    (
+        PropertyStore::Container,
+        DEVPKEY_DeviceContainer_FriendlyName,
+    ),
+    (
+        PropertyStore::Interface,
+        DEVPKEY_DeviceInterface_FriendlyName,
+    ),
+    (PropertyStore::Device, DEVPKEY_Device_FriendlyName),
+    (PropertyStore::Device, DEVPKEY_Device_DeviceDesc),
+];
+
+/// Calls the lookups in order and returns the first value `pick` accepts
+///
+/// The lookups that fail with [`win::Error::NOT_FOUND`] are skipped like the values `pick`
+/// rejects, while any other error is returned right away, without calling the next lookups
+fn first_of<T, F>(
+    lookups: impl IntoIterator<Item = F>,
+    mut pick: impl FnMut(DevProperty) -> Option<T>,
+) -> win::Result<Option<T>>
+where
+    F: FnOnce() -> win::Result<DevProperty>,
+{
+    for lookup in lookups {
+        match lookup() {
+            Ok(value) => {
+                if let Some(v) = pick(value) {
+                    return Ok(Some(v));
+                }
+            }
+            Err(win::Error::NOT_FOUND) => (),
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(None)
+}
+
+/// Accepts the `Bool` values, see [`first_of()`]
+fn bool_value(value: DevProperty) -> Option<bool> {
+    match value {
+        DevProperty::Bool(v) => Some(v),
+        _ => None,
+    }
+}
+
+/// Accepts the `String` values that aren't empty, see [`first_of()`]
+fn non_empty_string(value: DevProperty) -> Option<String> {
+    match value {
+        DevProperty::String(v) if !v.is_empty() => Some(v),
+        _ => None,
+    }
+}
+
+/// An owned copy of the data of a device interface
+///
+/// Unlike [`DevInterfaceData`] this doesn't hold any handle to the device set it was
+/// retrieved from, so it can outlive it and be moved across threads
+pub struct OwnedDevInterface {
+    /// The GUID of the device interface class
+    pub class: GUID,
+    /// The `SPINT_*` flags of the device interface
+    pub flags: DWORD,
+    /// The path of the device interface, as returned by [`DevInterfaceData::fetch_path()`]
+    pub path: Vec<u8>,
+    /// All the properties of the device interface, in the order they were listed,
+    /// each with its value or the error that prevented fetching it
+    pub properties: Vec<(DEVPROPKEY, win::Result<DevProperty>)>,
+}
+
+/// Another name of [`OwnedDevInterface`], returned by [`DevInterfaceData::to_owned_info()`]
+pub type OwnedDeviceInfo = OwnedDevInterface;
+
+// NOTE: the owned values are meant to be handed to other threads, so they must stay `Send`
+const _: () = {
+    fn assert_send<T: Send>() {}
+    let _ = assert_send::<OwnedDevInterface>;
+    let _ = assert_send::<DeviceSnapshot>;
+};
+
+/// A snapshot of a device interface made only of plain Rust types,
+/// meant to be serialized (with the `serde` feature)
+///
+/// This is returned by [`DevInterfaceData::snapshot()`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceSnapshot {
+    /// The GUID of the device interface class
+    pub class: GuidWrap,
+    /// The path of the device interface, converted lossily to UTF-8
+    pub path: String,
+    /// Whether or not the device interface is active
+    pub active: bool,
+    /// Whether or not the device interface is the default for its class
+    pub default: bool,
+    /// Whether or not the device interface is removed
+    pub removed: bool,
+    /// All the properties of the device interface, in the order they were listed,
+    /// except the ones whose value couldn't be fetched
+    pub properties: Vec<SnapshotProperty>,
+}
+
+/// A property of a [`DeviceSnapshot`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotProperty {
+    /// The key of the property
+    pub key: DevPropKey,
+    /// The name of the property, if it's a known one
+    pub name: Option<&'static str>,
+    /// The value of the property
+    pub value: DevProperty,
+}
+
+impl From<OwnedDevInterface> for DeviceSnapshot {
+    fn from(owned: OwnedDevInterface) -> Self {
+        let path: Vec<u16> = owned
+            .path
+            .chunks_exact(2)
+            .map(|v| u16::from_ne_bytes([v[0], v[1]]))
+            .collect();
+        let properties = owned
+            .properties
+            .into_iter()
+            .filter_map(|(key, value)| {
+                Some(SnapshotProperty {
+                    key: DevPropKey(key),
+                    name: devprop::names::name_of(&key),
+                    value: value.ok()?,
+                })
+            })
+            .collect();
+        Self {
+            class: GuidWrap(owned.class),
+            path: String::from_utf16_lossy(&path),
+            active: InterfaceFlags(owned.flags).is_active(),
+            default: InterfaceFlags(owned.flags).is_default(),
+            removed: InterfaceFlags(owned.flags).is_removed(),
+            properties,
+        }
+    }
+}
+
+/// The GUID is serialized as its [`Display`](std::fmt::Display) string
+#[cfg(feature = "serde")]
+impl serde::Serialize for DeviceSnapshot {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("DeviceSnapshot", 6)?;
+        state.serialize_field("class", &self.class.to_string())?;
+        state.serialize_field("path", &self.path)?;
+        state.serialize_field("active", &self.active)?;
+        state.serialize_field("default", &self.default)?;
+        state.serialize_field("removed", &self.removed)?;
+        state.serialize_field("properties", &self.properties)?;
+        state.end()
+    }
+}
+
+/// The key is serialized as its [`Display`](std::fmt::Display) string
+#[cfg(feature = "serde")]
+impl serde::Serialize for SnapshotProperty {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("SnapshotProperty", 3)?;
+        state.serialize_field("key", &self.key.to_string())?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("value", &self.value)?;
+        state.end()
+    }
+}
+
+/// Deserializes the object written by the `Serialize` implementation,
+/// the name of each property is looked up again from its key
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for DeviceSnapshot {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        const FIELDS: &[&str] = &[
+            "class",
+            "path",
+            "active",
+            "default",
+            "removed",
+            "properties",
+        ];
+        deserializer.deserialize_struct("DeviceSnapshot", FIELDS, SnapshotVisitor)
+    }
+}
+
+/// The visitor of the object of a serialized [`DeviceSnapshot`]
+#[cfg(feature = "serde")]
+struct SnapshotVisitor;
+
+#[cfg(feature = "serde")]
+impl<'de> serde::de::Visitor<'de> for SnapshotVisitor {
+    type Value = DeviceSnapshot;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "a device snapshot object")
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(
+        self,
+        mut map: A,
+    ) -> Result<DeviceSnapshot, A::Error> {
+        use serde::de::{Error, IgnoredAny};
+
+        let mut class = None;
+        let mut path = None;
+        let mut active = None;
+        let mut default = None;
+        let mut removed = None;
+        let mut properties = None;
+        while let Some(field) = map.next_key::<String>()? {
+            match field.as_str() {
+                "class" => {
+                    let guid = map.next_value::<String>()?;
+                    class = Some(guid.parse::<GuidWrap>().map_err(A::Error::custom)?);
+                }
+                "path" => path = Some(map.next_value()?),
+                "active" => active = Some(map.next_value()?),
+                "default" => default = Some(map.next_value()?),
+                "removed" => removed = Some(map.next_value()?),
+                "properties" => properties = Some(map.next_value()?),
+                _ => drop(map.next_value::<IgnoredAny>()?),
+            }
+        }
+        Ok(DeviceSnapshot {
+            class: class.ok_or_else(|| A::Error::missing_field("class"))?,
+            path: path.ok_or_else(|| A::Error::missing_field("path"))?,
+            active: active.ok_or_else(|| A::Error::missing_field("active"))?,
+            default: default.ok_or_else(|| A::Error::missing_field("default"))?,
+            removed: removed.ok_or_else(|| A::Error::missing_field("removed"))?,
+            properties: properties.ok_or_else(|| A::Error::missing_field("properties"))?,
+        })
+    }
+}
+
+/// Deserializes the object written by the `Serialize` implementation,
+/// the name is looked up again from the key, ignoring the serialized one
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SnapshotProperty {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        const FIELDS: &[&str] = &["key", "name", "value"];
+        deserializer.deserialize_struct("SnapshotProperty", FIELDS, SnapshotPropertyVisitor)
+    }
+}
+
+/// The visitor of the object of a serialized [`SnapshotProperty`]
+#[cfg(feature = "serde")]
+struct SnapshotPropertyVisitor;
+
+#[cfg(feature = "serde")]
+impl<'de> serde::de::Visitor<'de> for SnapshotPropertyVisitor {
+    type Value = SnapshotProperty;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "a snapshot property object")
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(
+        self,
+        mut map: A,
+    ) -> Result<SnapshotProperty, A::Error> {
+        use serde::de::{Error, IgnoredAny};
+
+        let mut key = None;
+        let mut value = None;
+        while let Some(field) = map.next_key::<String>()? {
+            match field.as_str() {
+                "key" => {
+                    let raw = map.next_value::<String>()?;
+                    let parsed = DevPropKey::parse(&raw);
+                    key = Some(parsed.ok_or_else(|| A::Error::custom("invalid property key"))?);
+                }
+                "value" => value = Some(map.next_value()?),
+                _ => drop(map.next_value::<IgnoredAny>()?),
+            }
+        }
+        let key: DevPropKey = key.ok_or_else(|| A::Error::missing_field("key"))?;
+        Ok(SnapshotProperty {
+            key,
+            name: devprop::names::name_of(&key.0),
+            value: value.ok_or_else(|| A::Error::missing_field("value"))?,
+        })
+    }
+}
+
+/// Converts an I/O error into the Win32 error it wraps, [`win::Error::INTERNAL_ERROR`]
+/// if it doesn't wrap one
+#[cfg(feature = "json")]
+fn win_error(err: std::io::Error) -> win::Error {
+    match err.raw_os_error() {
+        Some(code) => win::Error::from_code(code as u32),
+        None => win::Error::INTERNAL_ERROR,
+    }
+}
+
+/// Scans the device interfaces of the given classes currently present and saves their
+/// [`DeviceSnapshot`]s to the file at `path` as a JSON array, see [`load_scan()`]
+///
+/// Each device is written as soon as it's fetched, so the whole scan is never held in memory
+#[cfg(feature = "json")]
+pub fn save_scan(path: &std::path::Path, guids: &[GUID]) -> win::Result<()> {
+    use std::io::Write;
+
+    let mut file = std::io::BufWriter::new(File::create(path).map_err(win_error)?);
+    let mut separator = "";
+    file.write_all(b"[").map_err(win_error)?;
+    for guid in guids {
+        let set = DevInterfaceSet::fetch_present_for_class(guid)?;
+        for data in set.enumerate(guid) {
+            let snapshot = data?.snapshot()?;
+            file.write_all(separator.as_bytes()).map_err(win_error)?;
+            serde_json::to_writer(&mut file, &snapshot).map_err(|err| win_error(err.into()))?;
+            separator = ",";
+        }
+    }
+    file.write_all(b"]").map_err(win_error)?;
+    file.flush().map_err(win_error)
+}
+
+/// Loads the [`DeviceSnapshot`]s saved by [`save_scan()`] from the file at `path`
+///
+/// An error of kind [`InvalidData`](std::io::ErrorKind::InvalidData) is returned
+/// if the file isn't a valid scan
+#[cfg(feature = "json")]
+pub fn load_scan(path: &std::path::Path) -> std::io::Result<Vec<DeviceSnapshot>> {
+    let file = std::io::BufReader::new(File::open(path)?);
+    Ok(serde_json::from_reader(file)?)
+}
+
+/// Fetches the owned data of all the device interfaces of the given class currently present
+fn scan_class(guid: GUID) -> win::Result<Vec<OwnedDevInterface>> {
+    let set = DevInterfaceSet::fetch_present_for_class(&guid)?;
+    let devices = set
+        .enumerate(&guid)
+        .map(|data| data?.fetch_owned())
+        .collect();
+    devices
+}
+
+/// Fetches the owned data of all the device interfaces of the given classes currently present
+///
+/// The classes are scanned one after the other, see [`scan_storage_devices_parallel()`]
+/// for the multi-threaded version
+pub fn scan_storage_devices(guids: &[GUID]) -> win::Result<Vec<OwnedDevInterface>> {
+    let mut devices = Vec::new();
+    for &guid in guids {
+        devices.extend(scan_class(guid)?);
+    }
+    Ok(devices)
+}
+
+/// Fetches the owned data of all the device interfaces of the storage classes
+/// (see [`storage_interface_guids()`]) currently present, scanning each class on a separate
+/// [`rayon`] task
+///
+/// The result contains the same devices, in the same order, as [`scan_storage_devices()`]
+/// given the same classes
+///
+/// # Thread safety
+///
+/// A [`DevInterfaceSet`] wraps a raw `HDEVINFO` and it's not [`Send`], so it's never shared:
+/// each task fetches its own set, enumerates the single class it was assigned to and drops
+/// the set before returning.
+/// Only the [`OwnedDevInterface`] values, which hold no handle, cross the thread boundaries
+/// to be merged in the final result.
+#[cfg(feature = "parallel")]
+pub fn scan_storage_devices_parallel() -> win::Result<Vec<OwnedDevInterface>> {
+    use rayon::prelude::*;
+
+    let classes = storage_interface_guids()
+        .par_iter()
+        .map(|&(_, guid)| scan_class(guid))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(classes.into_iter().flatten().collect())
+}
+
+/// The parts of [`DevProperty`] that need `std` or call the WinAPI
+impl DevProperty {
+    /// Converts a `FileTime` or a `Date` value into a [`SystemTime`]
+    ///
+    /// `None` is returned for other variants, or if the time can't be represented
+    pub fn to_system_time(&self) -> Option<SystemTime> {
+        let ticks = match *self {
+            DevProperty::FileTime(v) => v,
+            DevProperty::Date(v) => {
+                let secs = ole_date_to_unix_secs(v);
+                return if secs >= 0.0 {
+                    UNIX_EPOCH.checked_add(Duration::try_from_secs_f64(secs).ok()?)
+                } else {
+                    UNIX_EPOCH.checked_sub(Duration::try_from_secs_f64(-secs).ok()?)
+                };
+            }
+            _ => return None,
+        };
+        let unix_epoch = FILETIME_UNIX_EPOCH_DAYS as u64 * 86400 * FILETIME_TICKS_PER_SEC;
+        let duration = |ticks: u64| {
+            Duration::new(
+                ticks / FILETIME_TICKS_PER_SEC,
+                (ticks % FILETIME_TICKS_PER_SEC * 100) as u32,
+            )
+        };
+        if ticks >= unix_epoch {
+            UNIX_EPOCH.checked_add(duration(ticks - unix_epoch))
+        } else {
+            UNIX_EPOCH.checked_sub(duration(unix_epoch - ticks))
+        }
+    }
+
+    /// Converts a `SecurityDescriptor` value into its SDDL string representation
+    ///
+    /// `None` is returned for other variants,
+    /// [`win::Error::INVALID_DATA`] if the bytes aren't a valid self-relative descriptor
+    pub fn to_sddl(&self) -> Option<win::Result<String>> {
+        let raw = match self {
+            DevProperty::SecurityDescriptor(v) => v,
+            _ => return None,
+        };
+        if !relative_descriptor_fits(raw) {
+            return Some(Err(win::Error::INVALID_DATA));
+        }
+        // NOTE: the bytes are copied into a DWORD buffer because the descriptor
+        //       is read through its fields, which need to be correctly aligned
+        let mut aligned: Vec<DWORD> = raw
+            .chunks(size_of::<DWORD>())
+            .map(|v| {
+                let mut bytes = [0; size_of::<DWORD>()];
+                bytes[..v.len()].copy_from_slice(v);
+                DWORD::from_ne_bytes(bytes)
+            })
+            .collect();
+
+        // SAFETY:
+        // https://docs.microsoft.com/en-us/windows/win32/api/securitybaseapi/nf-securitybaseapi-isvalidsecuritydescriptor#parameters
+        // `pSecurityDescriptor`: is correctly aligned and the parts its header refers to
+        //                        are within the buffer, as checked above
+        if unsafe { IsValidSecurityDescriptor(aligned.as_mut_ptr().cast()) } == BOOL::from(FALSE) {
+            return Some(Err(win::Error::INVALID_DATA));
+        }
+        // SAFETY: the descriptor has been validated above
+        let sd_len = unsafe { GetSecurityDescriptorLength(aligned.as_mut_ptr().cast()) };
+        if sd_len as usize > raw.len() {
+            return Some(Err(win::Error::INVALID_DATA));
+        }
+
+        let mut sddl = null_mut();
+        let mut len = 0;
+        // SAFETY:
+        // https://docs.microsoft.com/en-us/windows/win32/api/sddl/nf-sddl-convertsecuritydescriptortostringsecuritydescriptorw#parameters
+        // `SecurityDescriptor`: is a valid self-relative descriptor, correctly aligned,
+        //                       whose length doesn't exceed the buffer, as checked above
+        // `RequestedStringSDRevision`: must be `SDDL_REVISION_1`
+        // `SecurityInformation`: any combination of flags is allowed
+        // `StringSecurityDescriptor`: is a valid pointer to a `LPWSTR`
+        // `StringSecurityDescriptorLen`: is a valid pointer to a `ULONG`
+        let result = unsafe {
+            ConvertSecurityDescriptorToStringSecurityDescriptorW(
+                aligned.as_mut_ptr().cast(),
+                SDDL_REVISION_1.into(),
+                OWNER_SECURITY_INFORMATION
+                    | GROUP_SECURITY_INFORMATION
+                    | DACL_SECURITY_INFORMATION
+                    | SACL_SECURITY_INFORMATION,
+                &mut sddl,
+                &mut len,
+            )
+        };
+        if result != BOOL::from(TRUE) {
+            return Some(Err(win::Error::get()));
+        }
+        // SAFETY: on success `sddl` points to a string of `len` characters, null-terminator included
+        let utf16 = unsafe { std::slice::from_raw_parts(sddl, len as usize) };
+        let string = String::from_utf16_lossy(utf16.strip_suffix(&[0]).unwrap_or(utf16));
+        // SAFETY: the string was allocated by the call above and must be freed like this according to the docs
+        unsafe { LocalFree(sddl.cast()) };
+        Some(Ok(string))
+    }
+
+    /// Resolves an `IndirectString` value into the string resource it refers to,
+    /// using `SHLoadIndirectString` from `shlwapi`
+    ///
+    /// `None` is returned for other variants, the reference itself is kept in the value
+    /// so it's still available if the resolution fails
+    pub fn resolve(&self) -> Option<win::Result<String>> {
+        let source = match self {
+            DevProperty::IndirectString(v) => v,
+            _ => return None,
+        };
+        let source: Vec<u16> = source.encode_utf16().chain(iter::once(0)).collect();
+        // NOTE: the function doesn't report the required size, this is more than enough
+        //       for the names and descriptions that are stored as resources
+        let mut buffer = [0u16; INDIRECT_STRING_MAX_LEN];
+
+        // SAFETY:
+        // https://docs.microsoft.com/en-us/windows/win32/api/shlwapi/nf-shlwapi-shloadindirectstring#parameters
+        // `pszSource`: is a valid null-terminated string
+        // `pszOutBuf`: is a valid buffer of `cchOutBuf` characters
+        // `cchOutBuf`: is the size of the buffer
+        // `ppvReserved`: must be null
+        let result = unsafe {
+            SHLoadIndirectString(
+                source.as_ptr(),
+                buffer.as_mut_ptr(),
+                buffer.len() as UINT,
+                null_mut(),
+            )
+        };
+        if result != S_OK {
+            return Some(Err(hresult_error(result)));
+        }
+        let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+        Some(Ok(String::from_utf16_lossy(&buffer[..len])))
+    }
+}
+
+/// Returns whether the bytes start with the header of a self-relative security descriptor
+/// and the owner, the group and the ACLs it refers to are within them
+///
+/// This is checked before letting WinAPI validate the descriptor,
+/// because that follows the offsets in the header without knowing the length of the buffer
+fn relative_descriptor_fits(raw: &[u8]) -> bool {
+    if raw.len() < size_of::<SECURITY_DESCRIPTOR_RELATIVE>() {
+        return false;
+    }
+    let offset = |at: usize| {
+        let bytes = raw[at..at + size_of::<DWORD>()].try_into().unwrap();
+        DWORD::from_le_bytes(bytes) as usize
+    };
+    // NOTE: an offset of 0 means that the part is missing,
+    //       both a SID and an ACL start with an 8 bytes header that tells their length
+    let sid_fits = |at: usize| match offset(at) {
+        0 => true,
+        start => raw.get(start..).is_some_and(|sid| {
+            sid.len() >= 8 && 8 + size_of::<DWORD>() * usize::from(sid[1]) <= sid.len()
+        }),
+    };
+    let acl_fits = |at: usize| match offset(at) {
+        0 => true,
+        start => raw.get(start..).is_some_and(|acl| {
+            acl.len() >= 8 && usize::from(u16::from_le_bytes([acl[2], acl[3]])) <= acl.len()
+        }),
+    };
+    u16::from_le_bytes([raw[2], raw[3]]) & SE_SELF_RELATIVE != 0
+        && sid_fits(offset_of!(SECURITY_DESCRIPTOR_RELATIVE, Owner))
+        && sid_fits(offset_of!(SECURITY_DESCRIPTOR_RELATIVE, Group))
+        && acl_fits(offset_of!(SECURITY_DESCRIPTOR_RELATIVE, Sacl))
+        && acl_fits(offset_of!(SECURITY_DESCRIPTOR_RELATIVE, Dacl))
+}
+
+/// Converts a failed `HRESULT` into the Win32 error it wraps, or into an error
+/// with the raw `HRESULT` as its code if it doesn't wrap one
+fn hresult_error(result: HRESULT) -> win::Error {
+    match HRESULT_FACILITY(result) {
+        FACILITY_WIN32 => win::Error::from_code(HRESULT_CODE(result) as u32),
+        _ => win::Error::from_code(result as u32),
+    }
+}
+
+/// The size, in characters, of the buffer an `IndirectString` is resolved into
+const INDIRECT_STRING_MAX_LEN: usize = 1024;
+
+// NOTE: `winapi` doesn't expose `shlwapi`
+#[link(name = "shlwapi")]
+extern "system" {
+    fn SHLoadIndirectString(
+        pszSource: LPCWSTR,
+        pszOutBuf: LPWSTR,
+        cchOutBuf: UINT,
+        ppvReserved: *mut *mut c_void,
+    ) -> HRESULT;
+}
+
+/// The `DEV_OBJECT_TYPE` of the device containers (not defined by `winapi`)
+const DEV_OBJECT_TYPE_DEVICE_CONTAINER: u32 = 2;
+
+/// The `DEV_QUERY_FLAGS` without any flag set (not defined by `winapi`)
+const DEV_QUERY_FLAG_NONE: u32 = 0;
+
+// NOTE: `winapi` doesn't expose `devquery`
+#[link(name = "cfgmgr32")]
+extern "system" {
+    fn DevGetObjectProperties(
+        ObjectType: u32,
+        pszObjectId: LPCWSTR,
+        QueryFlags: ULONG,
+        cRequestedProperties: ULONG,
+        pRequestedProperties: *const DEVPROPCOMPKEY,
+        pcPropertyCount: *mut ULONG,
+        ppProperties: *mut *const DEVPROPERTY,
+    ) -> HRESULT;
+
+    fn DevFreeObjectProperties(cPropertyCount: ULONG, pProperties: *const DEVPROPERTY);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Turns the results into lookups for [`first_of()`]
+    fn lookups(
+        results: Vec<win::Result<DevProperty>>,
+    ) -> impl Iterator<Item = impl FnOnce() -> win::Result<DevProperty>> {
+        results.into_iter().map(|result| move || result)
+    }
+
+    /// Decodes the raw bytes like the fetched values are, failing on invalid UTF-16
+    fn decode(ty: DEVPROPTYPE, raw: Vec<u8>) -> win::Result<DevProperty> {
+        decode_value(ty, raw, false)
+    }
+
+    #[test]
+    fn reboot_required_falls_back_to_container() {
+        let device_unset = vec![Err(win::Error::NOT_FOUND), Ok(DevProperty::Bool(true))];
+        assert_eq!(first_of(lookups(device_unset), bool_value), Ok(Some(true)));
+
+        // NOTE: the container is not looked up when the device has the property
+        let device_set = vec![Ok(DevProperty::Bool(false)), Err(win::Error::ACCESS_DENIED)];
+        assert_eq!(first_of(lookups(device_set), bool_value), Ok(Some(false)));
+
+        let wrong_type = vec![Ok(DevProperty::U32(1)), Ok(DevProperty::Bool(true))];
+        assert_eq!(first_of(lookups(wrong_type), bool_value), Ok(Some(true)));
+
+        let unset = vec![Err(win::Error::NOT_FOUND), Err(win::Error::NOT_FOUND)];
+        assert_eq!(first_of(lookups(unset), bool_value), Ok(None));
+
+        let failed = vec![Err(win::Error::NOT_FOUND), Err(win::Error::ACCESS_DENIED)];
+        assert_eq!(
+            first_of(lookups(failed), bool_value),
+            Err(win::Error::ACCESS_DENIED)
+        );
+    }
+
+    #[test]
+    fn container_name_is_decoded_and_preferred() {
+        let raw: Vec<u8> = "SanDisk Ultra\0"
+            .encode_utf16()
+            .flat_map(u16::to_ne_bytes)
+            .collect();
+        let container = decode(DEVPROP_TYPE_STRING, raw);
+        assert_eq!(container, Ok(DevProperty::String("SanDisk Ultra".into())));
+
+        let interface = Ok(DevProperty::String("Interface".into()));
+        let names = vec![container.clone(), interface.clone()];
+        let name = first_of(lookups(names), non_empty_string);
+        assert_eq!(name, Ok(Some("SanDisk Ultra".into())));
+
+        let empty = Ok(DevProperty::String(String::new()));
+        let names = vec![empty, interface.clone()];
+        assert_eq!(
+            first_of(lookups(names), non_empty_string),
+            Ok(Some("Interface".into()))
+        );
+
+        let names = vec![Err(win::Error::NOT_FOUND), interface];
+        assert_eq!(
+            first_of(lookups(names), non_empty_string),
+            Ok(Some("Interface".into()))
+        );
+        assert_eq!(FRIENDLY_NAME_CHAIN[0].0, PropertyStore::Container);
+    }
+
+    #[test]
+    fn arrays_with_a_partial_element_are_invalid() {
+        let arrays = [
+            (DEVPROP_TYPE_INT16, 2),
+            (DEVPROP_TYPE_UINT16, 2),
+            (DEVPROP_TYPE_INT32, 4),
+            (DEVPROP_TYPE_UINT32, 4),
+            (DEVPROP_TYPE_INT64, 8),
+            (DEVPROP_TYPE_UINT64, 8),
+            (DEVPROP_TYPE_FLOAT, 4),
+            (DEVPROP_TYPE_DOUBLE, 8),
+            (DEVPROP_TYPE_GUID, size_of::<GUID>()),
+            (DEVPROP_TYPE_DEVPROPKEY, size_of::<DEVPROPKEY>()),
+        ];
+        for (ty, size) in arrays {
+            let ty = DEVPROP_TYPEMOD_ARRAY | ty;
+            let whole = decode(ty, vec![0; size * 2]).unwrap();
+            assert_eq!(whole.len(), Some(2), "{}", devprop::type_name(ty));
+
+            let partial = decode(ty, vec![0; size * 2 + 1]);
+            assert_eq!(
+                partial,
+                Err(win::Error::INVALID_DATA),
+                "{}",
+                devprop::type_name(ty)
+            );
+        }
+    }
+
+    #[test]
+    fn lone_surrogate_fails_strict_decoding() {
+        let raw: Vec<u8> = [0x61, 0xd800, 0x62, 0]
+            .into_iter()
+            .flat_map(u16::to_ne_bytes)
+            .collect();
+        let strict = decode(DEVPROP_TYPE_STRING, raw.clone());
+        assert_eq!(strict, Err(win::Error::INVALID_DATA));
+
+        let lossy = decode_value(DEVPROP_TYPE_STRING, raw, true);
+        assert_eq!(lossy, Ok(DevProperty::String("a\u{fffd}b".into())));
+    }
+
+    #[test]
+    fn empty_strings_are_decoded() {
+        let terminated = decode(DEVPROP_TYPE_STRING, vec![0, 0]);
+        assert_eq!(terminated, Ok(DevProperty::String(String::new())));
+
+        let unterminated = decode(DEVPROP_TYPE_STRING, Vec::new());
+        assert_eq!(unterminated, Ok(DevProperty::String(String::new())));
+
+        let odd = decode(DEVPROP_TYPE_STRING, vec![b'a', 0, 0]);
+        assert_eq!(odd, Err(win::Error::INVALID_DATA));
+    }
+
+    #[test]
+    fn utf16_from_an_odd_offset() {
+        // NOTE: the string starts at an odd offset of the buffer it's copied from,
+        //       the decoding doesn't depend on the alignment of the bytes
+        let mut buffer = vec![0xff];
+        buffer.extend(
+            "SD\u{e8}\u{1f4be}\0"
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes),
+        );
+        let raw = buffer[1..].to_vec();
+        assert_eq!(raw[..4], [b'S', 0, b'D', 0]);
+
+        let string = decode(DEVPROP_TYPE_STRING, raw);
+        assert_eq!(string, Ok(DevProperty::String("SD\u{e8}\u{1f4be}".into())));
+    }
+
+    #[test]
+    fn arrays_are_decoded_per_element() {
+        let raw = [1u32, 0xdead_beef, u32::MAX]
+            .into_iter()
+            .flat_map(u32::to_ne_bytes)
+            .collect();
+        let array = decode(DEVPROP_TYPEMOD_ARRAY | DEVPROP_TYPE_UINT32, raw);
+        assert_eq!(
+            array,
+            Ok(DevProperty::U32Array(vec![1, 0xdead_beef, u32::MAX]))
+        );
+
+        let guids = [GUID_DEVINTERFACE_DISK, GUID_DEVINTERFACE_VOLUME];
+        let raw = guids
+            .iter()
+            .flat_map(|guid| {
+                let mut bytes = guid.Data1.to_ne_bytes().to_vec();
+                bytes.extend(guid.Data2.to_ne_bytes());
+                bytes.extend(guid.Data3.to_ne_bytes());
+                bytes.extend(guid.Data4);
+                bytes
+            })
+            .collect();
+        let array = decode(DEVPROP_TYPEMOD_ARRAY | DEVPROP_TYPE_GUID, raw);
+        assert_eq!(
+            array,
+            Ok(DevProperty::GuidArray(guids.map(GuidWrap).to_vec()))
+        );
+    }
+
+    #[test]
+    fn prop_type_is_decoded() {
+        let raw = DEVPROP_TYPE_UINT32.to_ne_bytes().to_vec();
+        let value = decode(DEVPROP_TYPE_DEVPROPTYPE, raw).unwrap();
+        assert_eq!(value, DevProperty::PropType(DEVPROP_TYPE_UINT32));
+        assert_eq!(value.to_string(), devprop::type_name(DEVPROP_TYPE_UINT32));
+
+        let short = decode(DEVPROP_TYPE_DEVPROPTYPE, vec![7, 0]);
+        assert_eq!(short, Err(win::Error::INVALID_DATA));
+    }
+
+    #[test]
+    fn dates_to_system_time() {
+        let unix_epoch = DevProperty::Date(25569.0);
+        assert_eq!(unix_epoch.to_system_time(), Some(UNIX_EPOCH));
+
+        let evening = DevProperty::Date(25569.75);
+        let expected = UNIX_EPOCH + Duration::from_secs(18 * 3600);
+        assert_eq!(evening.to_system_time(), Some(expected));
+
+        // NOTE: the fractional part of negative dates still moves forward in the day
+        let before_ole_epoch = DevProperty::Date(-1.25);
+        let expected = UNIX_EPOCH - Duration::from_secs(25569 * 86400 + 18 * 3600);
+        assert_eq!(before_ole_epoch.to_system_time(), Some(expected));
+
+        let file_time = DevProperty::FileTime(116_444_736_000_000_000);
+        assert_eq!(file_time.to_system_time(), Some(UNIX_EPOCH));
+        assert_eq!(DevProperty::F64(25569.0).to_system_time(), None);
+    }
+
+    #[test]
+    fn interface_flags_from_raw_bits() {
+        let flags = InterfaceFlags(SPINT_ACTIVE | SPINT_REMOVED);
+        assert!(flags.is_active());
+        assert!(!flags.is_default());
+        assert!(flags.is_removed());
+        assert_eq!(flags, InterfaceFlags::ACTIVE | InterfaceFlags::REMOVED);
+        assert!(flags.contains(InterfaceFlags::ACTIVE | InterfaceFlags::REMOVED));
+        assert!(!flags.contains(InterfaceFlags::DEFAULT));
+        assert_eq!(flags.0, SPINT_ACTIVE | SPINT_REMOVED);
+
+        assert_eq!(InterfaceFlags::default(), InterfaceFlags(0));
+        assert!(!InterfaceFlags(0).is_active());
+        assert!(InterfaceFlags(SPINT_DEFAULT).is_default());
+    }
+
+    #[test]
+    fn parse_device_path() {
+        let path =
+            r"\\?\usbstor#disk&ven_generic&prod_sd#1234&0#{53f56307-b6bf-11d0-94f2-00a0c91efb8b}";
+        let parsed = ParsedPath::parse(path).unwrap();
+        assert_eq!(parsed.instance, "usbstor#disk&ven_generic&prod_sd#1234&0");
+        assert_eq!(parsed.class, GuidWrap(GUID_DEVINTERFACE_DISK));
+        assert_eq!(parsed.reference, None);
+
+        let with_reference = format!("{path}\\global");
+        let parsed = ParsedPath::parse(&with_reference).unwrap();
+        assert_eq!(parsed.reference.as_deref(), Some("global"));
+
+        for malformed in [
+            "usbstor#disk#{53f56307-b6bf-11d0-94f2-00a0c91efb8b}",
+            r"\\?\usbstor",
+            r"\\?\#{53f56307-b6bf-11d0-94f2-00a0c91efb8b}",
+            r"\\?\usbstor#disk#53f56307",
+        ] {
+            assert_eq!(ParsedPath::parse(malformed), Err(win::Error::INVALID_DATA));
+        }
+    }
+
+    #[test]
+    fn same_path_in_different_case() {
+        let lower = r"\\?\usbstor#disk&ven_generic#1234&0#{53f56307-b6bf-11d0-94f2-00a0c91efb8b}";
+        let upper = lower.to_uppercase();
+        let wide = |s: &str| -> Vec<u16> { s.encode_utf16().collect() };
+
+        assert_eq!(wide_eq_ignore_case(&wide(lower), &wide(&upper)), Ok(true));
+        assert_eq!(
+            paths_eq_ignore_case(lower.as_ref(), upper.as_ref()),
+            Ok(true)
+        );
+
+        let other = lower.replace("1234", "5678");
+        assert_eq!(wide_eq_ignore_case(&wide(lower), &wide(&other)), Ok(false));
+        assert_eq!(
+            wide_eq_ignore_case(&wide(lower), &wide(&lower[1..])),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn string_variant_check() {
+        let string = DevProperty::String("SD Card".into());
+        assert_eq!(string_value(string), Ok("SD Card".to_string()));
+
+        let list = DevProperty::StringList(vec!["SD Card".into()]);
+        assert_eq!(string_value(list.clone()), Err(win::Error::MORE_DATA));
+        assert_eq!(
+            typed_value::<Vec<String>>(list),
+            Ok(vec!["SD Card".to_string()])
+        );
+
+        assert_eq!(
+            string_value(DevProperty::U32(1)),
+            Err(win::Error::INVALID_DATATYPE)
+        );
+        let string = DevProperty::String("SD Card".into());
+        assert_eq!(
+            typed_value::<Vec<String>>(string),
+            Err(win::Error::INVALID_DATATYPE)
+        );
+    }
+
+    #[test]
+    fn scalar_variant_check() {
+        // NOTE: this is how `DEVPKEY_Storage_Removable_Media` is decoded
+        let removable = decode(DEVPROP_TYPE_BOOLEAN, vec![DEVPROP_TRUE as u8]);
+        assert_eq!(typed_value::<bool>(removable.unwrap()), Ok(true));
+
+        assert_eq!(typed_value::<u32>(DevProperty::U32(7)), Ok(7));
+        assert_eq!(typed_value::<u64>(DevProperty::U64(7)), Ok(7));
+        let guid = typed_value::<GUID>(DevProperty::Guid(GuidWrap(GUID_DEVINTERFACE_DISK)));
+        assert_eq!(guid.map(GuidWrap), Ok(GuidWrap(GUID_DEVINTERFACE_DISK)));
+
+        assert_eq!(
+            typed_value::<u32>(DevProperty::U64(7)),
+            Err(win::Error::INVALID_DATATYPE)
+        );
+        assert_eq!(
+            typed_value::<bool>(DevProperty::U8(1)),
+            Err(win::Error::INVALID_DATATYPE)
+        );
+    }
+
+    /// `O:BAG:SYD:`, a self-relative descriptor with an owner, a group and an empty DACL
+    const OWNER_GROUP_EMPTY_DACL: [u8; 56] = [
+        1, 0, 0x04, 0x80, 20, 0, 0, 0, 36, 0, 0, 0, 0, 0, 0, 0, 48, 0, 0, 0, // header
+        1, 2, 0, 0, 0, 0, 0, 5, 32, 0, 0, 0, 0x20, 0x02, 0, 0, // S-1-5-32-544
+        1, 1, 0, 0, 0, 0, 0, 5, 18, 0, 0, 0, // S-1-5-18
+        2, 0, 8, 0, 0, 0, 0, 0, // ACL with no ACEs
+    ];
+
+    #[test]
+    fn self_relative_descriptor_to_sddl() {
+        let sd = DevProperty::SecurityDescriptor(OWNER_GROUP_EMPTY_DACL.to_vec());
+        assert_eq!(sd.to_sddl(), Some(Ok("O:BAG:SYD:".to_string())));
+
+        assert_eq!(
+            DevProperty::Binary(OWNER_GROUP_EMPTY_DACL.to_vec()).to_sddl(),
+            None
+        );
+    }
+
+    #[test]
+    fn malformed_descriptor_is_invalid() {
+        let invalid = Some(Err(win::Error::INVALID_DATA));
+        let sddl = |raw: &[u8]| DevProperty::SecurityDescriptor(raw.to_vec()).to_sddl();
+
+        assert_eq!(sddl(&[]), invalid);
+        assert_eq!(sddl(&OWNER_GROUP_EMPTY_DACL[..12]), invalid);
+        // NOTE: the header is whole, but the group and the DACL are cut off
+        assert_eq!(sddl(&OWNER_GROUP_EMPTY_DACL[..40]), invalid);
+
+        let mut absolute = OWNER_GROUP_EMPTY_DACL;
+        absolute[3] = 0;
+        assert_eq!(sddl(&absolute), invalid);
+
+        let mut past_the_end = OWNER_GROUP_EMPTY_DACL;
+        past_the_end[16] = 0xff;
+        assert_eq!(sddl(&past_the_end), invalid);
+    }
+
+    #[test]
+    fn interface_class_lookups() {
+        let disk = GUID_DEVINTERFACE_DISK;
+        assert_eq!(interface_class_name(&disk), Some("GUID_DEVINTERFACE_DISK"));
+        assert_eq!(
+            interface_class_guid("GUID_DEVINTERFACE_DISK").map(GuidWrap),
+            Some(GuidWrap(disk))
+        );
+
+        let unknown = GUID {
+            Data1: 0,
+            Data2: 0,
+            Data3: 0,
+            Data4: [0; 8],
+        };
+        assert_eq!(interface_class_name(&unknown), None);
+        assert!(interface_class_guid("GUID_DEVINTERFACE_FLOPPY_DISK").is_none());
+        assert!(interface_class_guid("guid_devinterface_disk").is_none());
+
+        for &(name, guid) in storage_interface_guids() {
+            assert_eq!(interface_class_name(&guid), Some(name));
+        }
+    }
+
+    #[test]
+    fn fetch_by_index() {
+        let guid = GUID_DEVINTERFACE_VOLUME;
+        let set = DevInterfaceSet::fetch_present_for_class(&guid).unwrap();
+
+        let first = DevInterfaceData::fetch(&set, 0, &guid).unwrap();
+        let enumerated = set.enumerate(&guid).next().transpose().unwrap();
+        assert_eq!(
+            first.map(|data| data.fetch_path()),
+            enumerated.map(|data| data.fetch_path()),
+        );
+
+        let past_the_end = DevInterfaceData::fetch(&set, DWORD::MAX - 1, &guid).unwrap();
+        assert!(past_the_end.is_none());
+    }
+
+    #[test]
+    fn snapshot_leaves_out_failed_properties() {
+        let path = r"\\?\usbstor#disk&ven_generic#1234&0#{53f56307-b6bf-11d0-94f2-00a0c91efb8b}";
+        let owned = OwnedDevInterface {
+            class: GUID_DEVINTERFACE_DISK,
+            flags: SPINT_ACTIVE,
+            path: path.encode_utf16().flat_map(u16::to_ne_bytes).collect(),
+            properties: vec![
+                (DEVPKEY_Device_Address, Err(win::Error::ACCESS_DENIED)),
+                (
+                    DEVPKEY_Device_FriendlyName,
+                    Ok(DevProperty::String("SD Card".into())),
+                ),
+            ],
+        };
+        let snapshot = DeviceSnapshot::from(owned);
+        assert_eq!(snapshot.path, path);
+        assert!(snapshot.active);
+        assert_eq!(
+            snapshot.properties,
+            [SnapshotProperty {
+                key: DevPropKey(DEVPKEY_Device_FriendlyName),
+                name: devprop::names::name_of(&DEVPKEY_Device_FriendlyName),
+                value: DevProperty::String("SD Card".into()),
+            }]
+        );
+    }
+
+    #[test]
+    fn contains_an_enumerated_path() {
+        let guid = GUID_DEVINTERFACE_VOLUME;
+        let set = DevInterfaceSet::fetch_present_for_class(&guid).unwrap();
+        let Some(data) = set.enumerate(&guid).next().transpose().unwrap() else {
+            return;
+        };
+        let path = data.fetch_path_utf8().unwrap();
+        assert_eq!(set.contains_path(path.as_ref()), Ok(true));
+        assert_eq!(set.contains_path(path.to_uppercase().as_ref()), Ok(true));
+
+        let missing = ParsedPath::parse(&path)
+            .map(|parsed| format!(r"\\?\missing#device#0#{:#}", parsed.class));
+        assert_eq!(set.contains_path(missing.unwrap().as_ref()), Ok(false));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_scan_matches_serial() {
+        let guids: Vec<GUID> = storage_interface_guids()
+            .iter()
+            .map(|&(_, guid)| guid)
+            .collect();
+        // NOTE: only the devices are compared, some property values may change between the scans
+        let devices = |devices: Vec<OwnedDevInterface>| -> Vec<(GuidWrap, Vec<u8>)> {
+            devices
+                .into_iter()
+                .map(|device| (GuidWrap(device.class), device.path))
+                .collect()
+        };
+
+        let serial = devices(scan_storage_devices(&guids).unwrap());
+        let parallel = devices(scan_storage_devices_parallel().unwrap());
+        assert_eq!(parallel, serial);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn snapshot_json_round_trip() {
+        let property = |key, value| SnapshotProperty {
+            key: DevPropKey(key),
+            name: devprop::names::name_of(&key),
+            value,
+        };
+        let snapshots = vec![DeviceSnapshot {
+            class: GuidWrap(GUID_DEVINTERFACE_DISK),
+            path: r"\\?\usbstor#disk&ven_generic#1234&0#{53f56307-b6bf-11d0-94f2-00a0c91efb8b}"
+                .to_string(),
+            active: true,
+            default: false,
+            removed: false,
+            properties: vec![
+                property(
+                    DEVPKEY_Device_FriendlyName,
+                    DevProperty::String("SD Card".into()),
+                ),
+                property(
+                    DEVPKEY_Device_HardwareIds,
+                    DevProperty::StringList(vec!["A".into()]),
+                ),
+                property(DEVPKEY_Device_Address, DevProperty::U32(7)),
+                property(
+                    DEVPKEY_Device_ClassGuid,
+                    DevProperty::Guid(GuidWrap(GUID_DEVINTERFACE_VOLUME)),
+                ),
+                property(
+                    DEVPKEY_Device_Capabilities,
+                    DevProperty::Binary(vec![0, 0xab, 0xff]),
+                ),
+                property(
+                    DEVPKEY_Device_RemovalPolicy,
+                    DevProperty::Decimal({
+                        let mut raw = [0; 16];
+                        raw[2] = 2;
+                        raw[3] = 0x80;
+                        raw[8..16].copy_from_slice(&12345u64.to_ne_bytes());
+                        raw
+                    }),
+                ),
+                property(
+                    DEVPKEY_Device_Model,
+                    DevProperty::PropType(DEVPROP_TYPE_STRING_LIST),
+                ),
+                property(
+                    DEVPKEY_Device_Parent,
+                    DevProperty::DevPropKey(DevPropKey(DEVPKEY_Device_Model)),
+                ),
+                property(
+                    DEVPKEY_Device_Siblings,
+                    DevProperty::F64Array(vec![-0.0, 1.5]),
+                ),
+                property(
+                    DEVPKEY_Device_Children,
+                    DevProperty::Unsupported {
+                        ty: 0x7f,
+                        data: Box::new([0xde, 0xad, 0x00]),
+                    },
+                ),
+            ],
+        }];
+
+        let json = serde_json::to_string(&snapshots).unwrap();
+        let loaded: Vec<DeviceSnapshot> = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, snapshots);
+
+        let path = std::env::temp_dir().join("sd-formatter-snapshot-round-trip.json");
+        std::fs::write(&path, &json).unwrap();
+        let loaded = load_scan(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), snapshots);
     }
 }
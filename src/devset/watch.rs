@@ -0,0 +1,257 @@
+use std::any::Any;
+use std::ffi::OsString;
+use std::mem::{size_of, zeroed};
+use std::os::windows::ffi::OsStringExt;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::ptr::{addr_of, null_mut};
+
+use winapi::shared::guiddef::GUID;
+use winapi::shared::minwindef::{BOOL, LPARAM, LRESULT, TRUE, UINT, WPARAM};
+use winapi::shared::windef::HWND;
+use winapi::shared::winerror::ERROR_CLASS_ALREADY_EXISTS;
+use winapi::um::dbt::{
+    DBT_DEVICEARRIVAL, DBT_DEVICEREMOVECOMPLETE, DBT_DEVTYP_DEVICEINTERFACE,
+    DEV_BROADCAST_DEVICEINTERFACE_W, DEV_BROADCAST_HDR,
+};
+use winapi::um::libloaderapi::GetModuleHandleW;
+use winapi::um::winuser::*;
+// NOTE: `winapi` only defines the `Ptr` variants on 64-bit targets,
+// on 32-bit ones they are macros that expand to the non-`Ptr` functions
+#[cfg(target_pointer_width = "32")]
+use winapi::um::winuser::{
+    GetWindowLongW as GetWindowLongPtrW, SetWindowLongW as SetWindowLongPtrW,
+};
+
+use crate::win;
+
+/// A change of a device interface, delivered to the callback given to [`watch()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceEvent {
+    /// A device interface has been added, its path is given
+    Arrived(OsString),
+    /// A device interface has been removed, its path is given
+    Removed(OsString),
+}
+
+/// The state stored in the user data of the window
+struct Receiver {
+    /// The callback given to [`watch()`]
+    callback: Box<dyn FnMut(DeviceEvent)>,
+    /// The payload of a panic of the callback, resumed by [`DeviceWatch::pump()`]
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+/// The name of the class of the message-only windows used to receive the notifications
+const WINDOW_CLASS: &str = "sd_formatter_device_watch";
+
+/// A subscription to the changes of the device interfaces, returned by [`watch()`]
+///
+/// The notifications are unregistered and the window is destroyed on drop
+///
+/// # Threading
+///
+/// The notifications are delivered as `WM_DEVICECHANGE` messages to a window owned by the thread
+/// that called [`watch()`], so the callback only runs when that same thread pumps the messages,
+/// e.g. by calling [`DeviceWatch::pump()`] in a loop.
+/// For this reason the value is not [`Send`].
+pub struct DeviceWatch {
+    /// The message-only window that receives the notifications
+    window: HWND,
+    /// The handles returned by `RegisterDeviceNotificationW`, one for each class
+    notifications: Vec<HDEVNOTIFY>,
+    /// The state stored in the user data of the window, owned by this value
+    receiver: *mut Receiver,
+}
+
+/// Subscribes to the arrival and the removal of the device interfaces of the given classes
+///
+/// The callback is called with each [`DeviceEvent`] while the messages are pumped,
+/// see [`DeviceWatch`] for the threading constraints.
+///
+/// # Panics
+///
+/// A panic of the callback can't unwind through the window procedure,
+/// so it's caught there and resumed by the [`DeviceWatch::pump()`] call that dispatched the message.
+///
+/// ```no_run
+/// # use sd_formatter::devset::{watch, DeviceEvent};
+/// # use winapi::um::winioctl::GUID_DEVINTERFACE_DISK;
+/// let watch = watch(&[GUID_DEVINTERFACE_DISK], |event| match event {
+///     DeviceEvent::Arrived(path) => println!("+ {path:?}"),
+///     DeviceEvent::Removed(path) => println!("- {path:?}"),
+/// })?;
+/// loop {
+///     watch.pump()?;
+/// }
+/// # Ok::<(), sd_formatter::Error>(())
+/// ```
+pub fn watch(
+    classes: &[GUID],
+    callback: impl FnMut(DeviceEvent) + 'static,
+) -> win::Result<DeviceWatch> {
+    let class_name: Vec<u16> = WINDOW_CLASS.encode_utf16().chain([0]).collect();
+    // SAFETY: NULL is allowed, it returns the handle of the current executable
+    let instance = unsafe { GetModuleHandleW(null_mut()) };
+
+    let class = WNDCLASSEXW {
+        cbSize: size_of::<WNDCLASSEXW>().try_into().unwrap(),
+        lpfnWndProc: Some(window_proc),
+        hInstance: instance,
+        lpszClassName: class_name.as_ptr(),
+        // SAFETY: this struct can be zero initialized
+        ..unsafe { zeroed() }
+    };
+    // SAFETY:
+    // https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-registerclassexw#parameters
+    // `unnamedParam1`: is a valid WNDCLASSEXW with the `cbSize` field initialized
+    // NOTE: the class is registered by the first call, the others fail because it already exists
+    if unsafe { RegisterClassExW(&class) } == 0 {
+        match win::Error::get() {
+            err if err.code() == ERROR_CLASS_ALREADY_EXISTS => (), // Ok
+            err => return Err(err),
+        }
+    }
+
+    // SAFETY:
+    // https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-createwindowexw#parameters
+    // `lpClassName`: is the null-terminated name of the class registered above
+    // `hWndParent`: `HWND_MESSAGE` creates a message-only window
+    // all the other parameters can be 0 or NULL
+    let window = unsafe {
+        CreateWindowExW(
+            0,
+            class_name.as_ptr(),
+            null_mut(),
+            0,
+            0,
+            0,
+            0,
+            0,
+            HWND_MESSAGE,
+            null_mut(),
+            instance,
+            null_mut(),
+        )
+    };
+    if window.is_null() {
+        return Err(win::Error::get());
+    }
+
+    // NOTE: from now on dropping the value cleans up what has been created so far
+    let mut watch = DeviceWatch {
+        window,
+        notifications: Vec::with_capacity(classes.len()),
+        receiver: Box::into_raw(Box::new(Receiver {
+            callback: Box::new(callback),
+            panic: None,
+        })),
+    };
+    // SAFETY: the window is valid and the receiver lives as long as it does
+    unsafe { SetWindowLongPtrW(window, GWLP_USERDATA, watch.receiver as _) };
+
+    for &class in classes {
+        let mut filter = DEV_BROADCAST_DEVICEINTERFACE_W {
+            dbcc_size: size_of::<DEV_BROADCAST_DEVICEINTERFACE_W>()
+                .try_into()
+                .unwrap(),
+            dbcc_devicetype: DBT_DEVTYP_DEVICEINTERFACE,
+            dbcc_classguid: class,
+            // SAFETY: this struct can be zero initialized
+            ..unsafe { zeroed() }
+        };
+        // SAFETY:
+        // https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-registerdevicenotificationw#parameters
+        // `hRecipient`: is a valid window handle
+        // `NotificationFilter`: is a valid DEV_BROADCAST_DEVICEINTERFACE_W
+        // `Flags`: the recipient is a window handle
+        let notification = unsafe {
+            RegisterDeviceNotificationW(
+                window.cast(),
+                (&mut filter as *mut DEV_BROADCAST_DEVICEINTERFACE_W).cast(),
+                DEVICE_NOTIFY_WINDOW_HANDLE,
+            )
+        };
+        if notification.is_null() {
+            return Err(win::Error::get());
+        }
+        watch.notifications.push(notification);
+    }
+    Ok(watch)
+}
+
+impl DeviceWatch {
+    /// Waits for the next message of the window and dispatches it,
+    /// calling the callback if it's a device change
+    ///
+    /// # Panics
+    ///
+    /// Resumes the panic of the callback, if it panicked
+    pub fn pump(&self) -> win::Result<()> {
+        // SAFETY: this struct can be zero initialized
+        let mut msg: MSG = unsafe { zeroed() };
+        // SAFETY:
+        // https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-getmessagew#parameters
+        // `lpMsg`: is a valid pointer to a MSG
+        // `hWnd`: is a valid window owned by this thread (because the value is not `Send`)
+        // `wMsgFilterMin` and `wMsgFilterMax`: 0 means no filter
+        let result = unsafe { GetMessageW(&mut msg, self.window, 0, 0) };
+        if result == -1 {
+            return Err(win::Error::get());
+        }
+        // SAFETY: the message has just been retrieved by `GetMessageW`
+        unsafe { DispatchMessageW(&msg) };
+        // SAFETY: the receiver is owned by this value and the window procedure is done with it
+        if let Some(payload) = unsafe { (*self.receiver).panic.take() } {
+            resume_unwind(payload);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for DeviceWatch {
+    fn drop(&mut self) {
+        for &notification in &self.notifications {
+            // SAFETY: the handle was returned by `RegisterDeviceNotificationW`
+            unsafe { UnregisterDeviceNotification(notification) };
+        }
+        // SAFETY: the window was created by `watch()`, on this same thread
+        unsafe { DestroyWindow(self.window) };
+        // SAFETY: the pointer was created by `Box::into_raw()` and the window that used it is gone
+        drop(unsafe { Box::from_raw(self.receiver) });
+    }
+}
+
+/// The procedure of the message-only windows, which forwards the device changes to the callback
+unsafe extern "system" fn window_proc(
+    window: HWND,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    if msg != WM_DEVICECHANGE || !matches!(wparam, DBT_DEVICEARRIVAL | DBT_DEVICEREMOVECOMPLETE) {
+        return DefWindowProcW(window, msg, wparam, lparam);
+    }
+    let header = lparam as *const DEV_BROADCAST_HDR;
+    if header.is_null() || (*header).dbch_devicetype != DBT_DEVTYP_DEVICEINTERFACE {
+        return BOOL::from(TRUE) as LRESULT;
+    }
+    let header = header as *const DEV_BROADCAST_DEVICEINTERFACE_W;
+    // NOTE: the name is a null-terminated string that extends past the end of the struct
+    let name = addr_of!((*header).dbcc_name) as *const u16;
+    let len = (0..).take_while(|&i| *name.add(i) != 0).count();
+    let path = OsString::from_wide(std::slice::from_raw_parts(name, len));
+
+    let receiver = GetWindowLongPtrW(window, GWLP_USERDATA) as *mut Receiver;
+    // NOTE: the user data is not set yet while the window is being created
+    if let Some(receiver) = receiver.as_mut() {
+        let event = match wparam {
+            DBT_DEVICEARRIVAL => DeviceEvent::Arrived(path),
+            _ => DeviceEvent::Removed(path),
+        };
+        // NOTE: unwinding out of an `extern "system"` function aborts the process
+        if let Err(payload) = catch_unwind(AssertUnwindSafe(|| (receiver.callback)(event))) {
+            receiver.panic = Some(payload);
+        }
+    }
+    BOOL::from(TRUE) as LRESULT
+}
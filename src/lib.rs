@@ -0,0 +1,34 @@
+//! # Features
+//!
+//! - `std` (default): everything that calls the WinAPI, that is the [`devset`] and [`win`] modules.
+//!   Without it the crate is `#![no_std]` (it still needs `alloc`) and only has the plain data types
+//!   of the [`devprop`] and [`value`] modules
+//! - `serde`: implements `Serialize` and `Deserialize` for the values and the snapshots (needs `std`)
+//! - `json`: conversion of the values into `serde_json` values and saving/loading scans
+//!   as JSON files (needs `std`, implies `serde`)
+//! - `parallel`: scanning the device classes on multiple threads with `rayon` (needs `std`)
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+/// Pairs each of the given identifiers with its name, as in `(stringify!(ident), ident)`
+macro_rules! with_name {
+    ($i:ident) => {
+        (stringify!($i), $i)
+    };
+
+    ([$($i:ident),* $(,)?]) => {
+        [ $( with_name!($i) ),* ]
+    }
+}
+
+pub mod devprop;
+#[cfg(feature = "std")]
+pub mod devset;
+pub mod value;
+#[cfg(feature = "std")]
+pub mod win;
+
+#[cfg(feature = "std")]
+pub use win::{Error, Result};
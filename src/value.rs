@@ -0,0 +1,1967 @@
+//! The plain data types of the properties, which don't call the WinAPI
+//!
+//! This module only needs `core` and `alloc`, so it's available without the `std` feature
+
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::collections::BTreeSet;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::iter;
+use core::mem::size_of;
+
+use winapi::shared::devpropdef::*;
+use winapi::shared::guiddef::*;
+
+use crate::devprop::{self, DevPropKey};
+
+#[derive(Debug, Clone)]
+pub enum DevProperty {
+    Empty,
+    Null,
+    Bool(bool),
+    BoolArray(Vec<bool>),
+    String(String),
+    StringList(Vec<String>),
+    /// A reference to a localizable string resource (like `@%SystemRoot%\system32\file.dll,-100`),
+    /// see [`DevProperty::resolve()`]
+    IndirectString(String),
+    I8(i8),
+    I8Array(Vec<i8>),
+    U8(u8),
+    U8Array(Vec<u8>),
+    I16(i16),
+    I16Array(Vec<i16>),
+    U16(u16),
+    U16Array(Vec<u16>),
+    I32(i32),
+    I32Array(Vec<i32>),
+    U32(u32),
+    U32Array(Vec<u32>),
+    I64(i64),
+    I64Array(Vec<i64>),
+    U64(u64),
+    U64Array(Vec<u64>),
+    F32(f32),
+    F32Array(Vec<f32>),
+    F64(f64),
+    F64Array(Vec<f64>),
+    Binary(Vec<u8>),
+    Guid(GuidWrap),
+    GuidArray(Vec<GuidWrap>),
+    /// A `FILETIME`, the number of 100-nanosecond intervals since January 1, 1601 (UTC)
+    FileTime(u64),
+    /// An OLE Automation `DECIMAL`, kept as raw bytes with the same layout:
+    /// 2 reserved bytes, the scale (the power of 10 the value is divided by), the sign
+    /// (`0x80` if negative), the high 32 bits and the low 64 bits of the 96-bit integer value
+    Decimal([u8; 16]),
+    /// An OLE Automation `CURRENCY`, a fixed-point number scaled by 10,000
+    Currency(i64),
+    /// An OLE Automation `DATE`, the number of days since December 30, 1899, with the time
+    /// of the day as the fractional part
+    Date(f64),
+    /// A self-relative `SECURITY_DESCRIPTOR`, kept as raw bytes
+    SecurityDescriptor(Vec<u8>),
+    DevPropKey(DevPropKey),
+    DevPropKeyArray(Vec<DevPropKey>),
+    /// A `DEVPROP_TYPE_*` type, formatted with [`devprop::type_name()`]
+    PropType(DEVPROPTYPE),
+    /// A value of a type this crate can't decode, kept as raw bytes along with its type
+    Unsupported {
+        ty: DEVPROPTYPE,
+        data: Box<[u8]>,
+    },
+}
+
+/// Floating point values are compared by their bit pattern, so that the comparison is
+/// an equivalence relation: `NaN`s with the same payload are equal, while `0.0` and `-0.0` are not
+impl PartialEq for DevProperty {
+    fn eq(&self, other: &Self) -> bool {
+        use DevProperty as P;
+
+        let f32eq = |a: &f32, b: &f32| a.to_bits() == b.to_bits();
+        let f64eq = |a: &f64, b: &f64| a.to_bits() == b.to_bits();
+
+        match (self, other) {
+            (P::Empty, P::Empty) | (P::Null, P::Null) => true,
+            (P::Bool(a), P::Bool(b)) => a == b,
+            (P::IndirectString(a), P::IndirectString(b)) => a == b,
+            (P::BoolArray(a), P::BoolArray(b)) => a == b,
+            (P::String(a), P::String(b)) => a == b,
+            (P::StringList(a), P::StringList(b)) => a == b,
+            (P::I8(a), P::I8(b)) => a == b,
+            (P::I8Array(a), P::I8Array(b)) => a == b,
+            (P::U8(a), P::U8(b)) => a == b,
+            (P::U8Array(a), P::U8Array(b)) => a == b,
+            (P::I16(a), P::I16(b)) => a == b,
+            (P::I16Array(a), P::I16Array(b)) => a == b,
+            (P::U16(a), P::U16(b)) => a == b,
+            (P::U16Array(a), P::U16Array(b)) => a == b,
+            (P::I32(a), P::I32(b)) => a == b,
+            (P::I32Array(a), P::I32Array(b)) => a == b,
+            (P::U32(a), P::U32(b)) => a == b,
+            (P::U32Array(a), P::U32Array(b)) => a == b,
+            (P::I64(a), P::I64(b)) => a == b,
+            (P::I64Array(a), P::I64Array(b)) => a == b,
+            (P::U64(a), P::U64(b)) => a == b,
+            (P::U64Array(a), P::U64Array(b)) => a == b,
+            (P::F32(a), P::F32(b)) => f32eq(a, b),
+            (P::F32Array(a), P::F32Array(b)) => {
+                a.len() == b.len() && iter::zip(a, b).all(|(a, b)| f32eq(a, b))
+            }
+            (P::F64(a), P::F64(b)) => f64eq(a, b),
+            (P::F64Array(a), P::F64Array(b)) => {
+                a.len() == b.len() && iter::zip(a, b).all(|(a, b)| f64eq(a, b))
+            }
+            (P::Binary(a), P::Binary(b)) => a == b,
+            (P::Guid(a), P::Guid(b)) => a == b,
+            (P::GuidArray(a), P::GuidArray(b)) => a == b,
+            (P::FileTime(a), P::FileTime(b)) => a == b,
+            (P::Decimal(a), P::Decimal(b)) => a == b,
+            (P::Currency(a), P::Currency(b)) => a == b,
+            (P::PropType(a), P::PropType(b)) => a == b,
+            (P::Date(a), P::Date(b)) => f64eq(a, b),
+            (P::SecurityDescriptor(a), P::SecurityDescriptor(b)) => a == b,
+            (P::DevPropKey(a), P::DevPropKey(b)) => a == b,
+            (P::DevPropKeyArray(a), P::DevPropKeyArray(b)) => a == b,
+            (
+                P::Unsupported {
+                    ty: a,
+                    data: a_data,
+                },
+                P::Unsupported {
+                    ty: b,
+                    data: b_data,
+                },
+            ) => a == b && a_data == b_data,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for DevProperty {}
+
+macro_rules! impl_dev_property_from {
+    ($($t:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$t> for DevProperty {
+                fn from(v: $t) -> Self {
+                    DevProperty::$variant(v.into())
+                }
+            }
+        )*
+    };
+}
+
+// NOTE: byte slices become `Binary` rather than `U8Array`, and `FILETIME`s can't be built
+//       from a plain `u64`, which always becomes `U64`
+impl_dev_property_from!(
+    bool => Bool,
+    i8 => I8,
+    u8 => U8,
+    i16 => I16,
+    u16 => U16,
+    i32 => I32,
+    u32 => U32,
+    i64 => I64,
+    u64 => U64,
+    f32 => F32,
+    f64 => F64,
+    String => String,
+    &str => String,
+    &[u8] => Binary,
+    GuidWrap => Guid,
+    DevPropKey => DevPropKey,
+);
+
+impl From<GUID> for DevProperty {
+    fn from(v: GUID) -> Self {
+        DevProperty::Guid(GuidWrap(v))
+    }
+}
+
+macro_rules! impl_try_from_dev_property {
+    ($($t:ty => $($variant:ident)|+),* $(,)?) => {
+        $(
+            impl TryFrom<DevProperty> for $t {
+                type Error = DevPropertyConversionError;
+
+                fn try_from(v: DevProperty) -> Result<Self, Self::Error> {
+                    match v {
+                        $( DevProperty::$variant(v) => Ok(v.into()), )+
+                        value => Err(DevPropertyConversionError {
+                            expected: stringify!($($variant)|+),
+                            value,
+                        }),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+// NOTE: the values are moved out of the variants, arrays included
+impl_try_from_dev_property!(
+    bool => Bool,
+    i8 => I8,
+    u8 => U8,
+    i16 => I16,
+    u16 => U16,
+    i32 => I32,
+    u32 => U32,
+    i64 => I64,
+    u64 => U64,
+    f32 => F32,
+    f64 => F64,
+    String => String | IndirectString,
+    GuidWrap => Guid,
+    DevPropKey => DevPropKey,
+    Vec<bool> => BoolArray,
+    Vec<String> => StringList,
+    Vec<i8> => I8Array,
+    Vec<u8> => U8Array | Binary,
+    Vec<i16> => I16Array,
+    Vec<u16> => U16Array,
+    Vec<i32> => I32Array,
+    Vec<u32> => U32Array,
+    Vec<i64> => I64Array,
+    Vec<u64> => U64Array,
+    Vec<f32> => F32Array,
+    Vec<f64> => F64Array,
+    Vec<GuidWrap> => GuidArray,
+);
+
+impl TryFrom<DevProperty> for GUID {
+    type Error = DevPropertyConversionError;
+
+    fn try_from(v: DevProperty) -> Result<Self, Self::Error> {
+        GuidWrap::try_from(v).map(|v| v.0)
+    }
+}
+
+/// The error returned when converting a [`DevProperty`] into a type that doesn't match its variant
+#[derive(Debug, Clone)]
+pub struct DevPropertyConversionError {
+    /// The variants that can be converted into the requested type, like `"U32"`
+    pub expected: &'static str,
+    /// The value that couldn't be converted, given back untouched
+    pub value: DevProperty,
+}
+
+impl core::fmt::Display for DevPropertyConversionError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let found = self.value.variant_name();
+        write!(f, "expected {}, found {found}", self.expected)
+    }
+}
+
+impl core::error::Error for DevPropertyConversionError {}
+
+/// The minimum ratio of printable ASCII characters a byte array must have
+/// to be rendered as text by [`DevProperty::display_smart()`]
+pub const PRINTABLE_TEXT_THRESHOLD: f64 = 0.9;
+
+impl DevProperty {
+    /// Returns the value of any integer variant widened to an [`i128`], which can hold all of them
+    ///
+    /// `None` is returned for non-integer variants
+    pub fn as_i128(&self) -> Option<i128> {
+        match *self {
+            DevProperty::I8(v) => Some(v.into()),
+            DevProperty::U8(v) => Some(v.into()),
+            DevProperty::I16(v) => Some(v.into()),
+            DevProperty::U16(v) => Some(v.into()),
+            DevProperty::I32(v) => Some(v.into()),
+            DevProperty::U32(v) => Some(v.into()),
+            DevProperty::I64(v) => Some(v.into()),
+            DevProperty::U64(v) => Some(v.into()),
+            _ => None,
+        }
+    }
+
+    /// Compares two numeric scalars of the same variant, floats are compared with `total_cmp`
+    ///
+    /// `None` is returned when the variants differ or aren't numeric scalars,
+    /// so e.g. a `U32` and an `U64` aren't comparable even if their values are
+    ///
+    /// ```no_run
+    /// # use sd_formatter::devset::DevProperty;
+    /// let mut values = vec![DevProperty::U32(3), DevProperty::U32(1), DevProperty::U32(2)];
+    /// values.sort_by(|a, b| a.partial_cmp_numeric(b).unwrap());
+    /// ```
+    pub fn partial_cmp_numeric(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        use DevProperty as P;
+
+        match (self, other) {
+            (P::I8(a), P::I8(b)) => Some(a.cmp(b)),
+            (P::U8(a), P::U8(b)) => Some(a.cmp(b)),
+            (P::I16(a), P::I16(b)) => Some(a.cmp(b)),
+            (P::U16(a), P::U16(b)) => Some(a.cmp(b)),
+            (P::I32(a), P::I32(b)) => Some(a.cmp(b)),
+            (P::U32(a), P::U32(b)) => Some(a.cmp(b)),
+            (P::I64(a), P::I64(b)) => Some(a.cmp(b)),
+            (P::U64(a), P::U64(b)) => Some(a.cmp(b)),
+            (P::F32(a), P::F32(b)) => Some(a.total_cmp(b)),
+            (P::F64(a), P::F64(b)) => Some(a.total_cmp(b)),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of any unsigned integer scalar variant widened to an [`u64`]
+    ///
+    /// `None` is returned for signed, array and non-integer variants
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            DevProperty::U8(v) => Some(v.into()),
+            DevProperty::U16(v) => Some(v.into()),
+            DevProperty::U32(v) => Some(v.into()),
+            DevProperty::U64(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of any signed integer scalar variant widened to an [`i64`]
+    ///
+    /// `None` is returned for unsigned, array and non-integer variants
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            DevProperty::I8(v) => Some(v.into()),
+            DevProperty::I16(v) => Some(v.into()),
+            DevProperty::I32(v) => Some(v.into()),
+            DevProperty::I64(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of any floating point scalar variant widened to an [`f64`]
+    ///
+    /// `None` is returned for every other variant, integers included
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            DevProperty::F32(v) => Some(v.into()),
+            DevProperty::F64(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a `Bool`, `None` for other variants
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            DevProperty::Bool(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a `String`, `None` for other variants
+    pub fn as_str(&self) -> Option<Cow<'_, str>> {
+        match self {
+            DevProperty::String(v) => Some(Cow::Borrowed(v)),
+            _ => None,
+        }
+    }
+
+    /// Decodes the UTF-16 code units (e.g. the ones returned by
+    /// [`Property::fetch_string_buf()`](crate::devset::Property::fetch_string_buf))
+    /// into a `String` value, `None` if they aren't valid UTF-16 (e.g. a lone surrogate)
+    pub fn try_from_utf16(units: &[u16]) -> Option<Self> {
+        String::from_utf16(units).ok().map(DevProperty::String)
+    }
+
+    /// Decodes the UTF-16 code units into a `String` value like [`Self::try_from_utf16()`],
+    /// replacing the invalid sequences with U+FFFD
+    pub fn from_utf16_lossy(units: &[u16]) -> Self {
+        DevProperty::String(String::from_utf16_lossy(units))
+    }
+
+    /// Returns the text of a `String`, `IndirectString` or `StringList` (joined by `"; "`),
+    /// `None` for other variants
+    ///
+    /// Unlike [`Self::to_string_lossy()`] this never renders non-textual values.
+    /// The strings of a value are always valid, as the invalid UTF-16 is detected when decoding
+    /// it: [`Property::fetch_value()`](crate::devset::Property::fetch_value) fails with
+    /// `INVALID_DATA`, while [`Property::fetch_value_lossy()`](crate::devset::Property::fetch_value_lossy)
+    /// replaces it, see also [`Self::try_from_utf16()`]
+    pub fn try_to_string(&self) -> Option<String> {
+        match self {
+            DevProperty::String(v) | DevProperty::IndirectString(v) => Some(v.clone()),
+            DevProperty::StringList(v) => Some(v.join("; ")),
+            _ => None,
+        }
+    }
+
+    /// Returns the text of a `String`, `IndirectString` or `StringList` (joined by `"; "`),
+    /// and the [`Display`](core::fmt::Display) rendering of the other variants
+    ///
+    /// The strings are already valid, the invalid UTF-16 sequences are replaced with U+FFFD
+    /// when decoding them with [`Property::fetch_value_lossy()`](crate::devset::Property::fetch_value_lossy)
+    pub fn to_string_lossy(&self) -> Cow<'_, str> {
+        match self {
+            DevProperty::String(v) | DevProperty::IndirectString(v) => Cow::Borrowed(v),
+            _ => Cow::Owned(self.try_to_string().unwrap_or_else(|| self.to_string())),
+        }
+    }
+
+    /// Returns the value of a `Guid`, `None` for other variants
+    pub fn as_guid(&self) -> Option<GUID> {
+        match *self {
+            DevProperty::Guid(v) => Some(v.0),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a `Guid` in its canonical form (see [`GuidWrap::to_canonical_string()`]),
+    /// `None` for other variants
+    pub fn guid_string(&self) -> Option<String> {
+        match self {
+            DevProperty::Guid(v) => Some(v.to_canonical_string()),
+            _ => None,
+        }
+    }
+
+    /// Returns whether or not the value is a `GuidArray` containing the given GUID
+    pub fn guid_array_contains(&self, guid: &GUID) -> bool {
+        match self {
+            DevProperty::GuidArray(v) => v.contains(&GuidWrap(*guid)),
+            _ => false,
+        }
+    }
+
+    /// Returns the GUIDs of a `GuidArray` without the duplicates, in the order they first appear,
+    /// `None` for other variants
+    pub fn guid_dedup(&self) -> Option<Box<[GUID]>> {
+        self.guid_filter(|_| true)
+    }
+
+    /// Returns the GUIDs of a `GuidArray` that are in the other `GuidArray` too,
+    /// without duplicates and in the order they first appear in `self`
+    ///
+    /// `None` is returned if any of the two values is not a `GuidArray`
+    pub fn guid_intersection(&self, other: &Self) -> Option<Box<[GUID]>> {
+        let other: BTreeSet<_> = other.guid_array()?.iter().collect();
+        self.guid_filter(|guid| other.contains(guid))
+    }
+
+    /// Returns the GUIDs of a `GuidArray` that are not in the other `GuidArray`,
+    /// without duplicates and in the order they first appear in `self`
+    ///
+    /// `None` is returned if any of the two values is not a `GuidArray`
+    pub fn guid_difference(&self, other: &Self) -> Option<Box<[GUID]>> {
+        let other: BTreeSet<_> = other.guid_array()?.iter().collect();
+        self.guid_filter(|guid| !other.contains(guid))
+    }
+
+    /// Returns the GUIDs of a `GuidArray`, `None` for other variants
+    fn guid_array(&self) -> Option<&[GuidWrap]> {
+        match self {
+            DevProperty::GuidArray(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the GUIDs of a `GuidArray` that satisfy the predicate, without the duplicates
+    /// and in the order they first appear, `None` for other variants
+    fn guid_filter(&self, mut predicate: impl FnMut(&GuidWrap) -> bool) -> Option<Box<[GUID]>> {
+        let mut seen = BTreeSet::new();
+        Some(
+            self.guid_array()?
+                .iter()
+                .filter(|guid| predicate(guid) && seen.insert(*guid))
+                .map(|guid| guid.0)
+                .collect(),
+        )
+    }
+
+    /// Returns the raw bytes of a `Binary`, `U8Array`, `SecurityDescriptor` or `Unsupported`,
+    /// `None` for other variants
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            DevProperty::Binary(v)
+            | DevProperty::U8Array(v)
+            | DevProperty::SecurityDescriptor(v) => Some(v),
+            DevProperty::Unsupported { data, .. } => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Returns an iterator over the elements of an array value, each as its scalar variant
+    ///
+    /// For example each element of a `U32Array` is yielded as a `U32`, `Binary` is treated
+    /// like a `U8Array` and `StringList` yields each `String`.
+    /// Non-array values are yielded once, as they are.
+    pub fn iter_scalars(&self) -> impl Iterator<Item = DevProperty> {
+        use DevProperty as P;
+
+        let scalars: Vec<_> = match self {
+            P::BoolArray(v) => v.iter().map(|&v| P::Bool(v)).collect(),
+            P::StringList(v) => v.iter().cloned().map(P::String).collect(),
+            P::I8Array(v) => v.iter().map(|&v| P::I8(v)).collect(),
+            P::U8Array(v) | P::Binary(v) => v.iter().map(|&v| P::U8(v)).collect(),
+            P::I16Array(v) => v.iter().map(|&v| P::I16(v)).collect(),
+            P::U16Array(v) => v.iter().map(|&v| P::U16(v)).collect(),
+            P::I32Array(v) => v.iter().map(|&v| P::I32(v)).collect(),
+            P::U32Array(v) => v.iter().map(|&v| P::U32(v)).collect(),
+            P::I64Array(v) => v.iter().map(|&v| P::I64(v)).collect(),
+            P::U64Array(v) => v.iter().map(|&v| P::U64(v)).collect(),
+            P::F32Array(v) => v.iter().map(|&v| P::F32(v)).collect(),
+            P::F64Array(v) => v.iter().map(|&v| P::F64(v)).collect(),
+            P::GuidArray(v) => v.iter().map(|&v| P::Guid(v)).collect(),
+            P::DevPropKeyArray(v) => v.iter().map(|&v| P::DevPropKey(v)).collect(),
+            _ => vec![self.clone()],
+        };
+        scalars.into_iter()
+    }
+
+    /// Renders the value choosing the most readable representation for byte arrays
+    ///
+    /// `I8Array`, `U8Array` and `Binary` values are rendered as a quoted ASCII string
+    /// when at least [`PRINTABLE_TEXT_THRESHOLD`] of the bytes are printable, and as hex otherwise.
+    /// Every other variant, and empty byte arrays (which are neither text nor binary data),
+    /// are rendered with their [`Display`](core::fmt::Display) implementation.
+    pub fn display_smart(&self) -> String {
+        let bytes: Vec<u8> = match self {
+            DevProperty::I8Array(v) => v.iter().map(|&v| v as u8).collect(),
+            DevProperty::U8Array(v) | DevProperty::Binary(v) => v.clone(),
+            _ => return self.to_string(),
+        };
+        if bytes.is_empty() {
+            return self.to_string();
+        }
+        let printable = bytes
+            .iter()
+            .filter(|v| v.is_ascii_graphic() || **v == b' ')
+            .count();
+        if printable as f64 >= bytes.len() as f64 * PRINTABLE_TEXT_THRESHOLD {
+            let text: String = bytes
+                .into_iter()
+                .flat_map(core::ascii::escape_default)
+                .map(char::from)
+                .collect();
+            format!("\"{text}\"")
+        } else {
+            bytes.iter().map(|v| format!("{v:02x}")).collect()
+        }
+    }
+
+    /// Encodes the bytes of a `Binary` or `U8Array` as base64 (standard alphabet, with padding),
+    /// `None` for other variants
+    ///
+    /// This is more compact than hex for interoperability, see [`Self::binary_from_base64()`]
+    /// for the inverse and `Base64Binary` (with the `serde` feature) to serialize binary data like this
+    pub fn binary_base64(&self) -> Option<String> {
+        match self {
+            DevProperty::Binary(v) | DevProperty::U8Array(v) => Some(base64_encode(v)),
+            _ => None,
+        }
+    }
+
+    /// Decodes a base64 string (standard alphabet, with padding) into a `Binary`,
+    /// `None` if the string isn't valid base64
+    ///
+    /// This is the inverse of [`Self::binary_base64()`]
+    pub fn binary_from_base64(encoded: &str) -> Option<Self> {
+        base64_decode(encoded).map(DevProperty::Binary)
+    }
+
+    /// Renders the bytes of a `Binary`, `U8Array`, `SecurityDescriptor` or `Unsupported`
+    /// as a classic hex dump,
+    /// `None` for other variants
+    ///
+    /// Each row has the offset, 16 bytes in hex and the same bytes as ASCII
+    /// (with `.` for the non-printable ones), e.g. `0000: de ad be ef  ....`,
+    /// the rows are separated by a newline
+    pub fn hex_dump(&self) -> Option<String> {
+        const ROW: usize = 16;
+
+        let bytes = self.as_bytes()?;
+        let rows: Vec<String> = bytes
+            .chunks(ROW)
+            .enumerate()
+            .map(|(i, row)| {
+                let hex: Vec<_> = row.iter().map(|v| format!("{v:02x}")).collect();
+                let ascii: String = row
+                    .iter()
+                    .map(|&v| match v.is_ascii_graphic() || v == b' ' {
+                        true => char::from(v),
+                        false => '.',
+                    })
+                    .collect();
+                // NOTE: the hex of the last row is padded to keep the ASCII column aligned
+                let width = ROW * 3 - 1;
+                format!("{:04x}: {:width$}  {ascii}", i * ROW, hex.join(" "))
+            })
+            .collect();
+        Some(rows.join("\n"))
+    }
+
+    /// Converts the value into a JSON value that mirrors its [`Display`](core::fmt::Display)
+    ///
+    /// Numbers are JSON numbers and arrays are JSON arrays, except for the 64-bit integers
+    /// that can't be represented exactly by a `f64`, which are strings to avoid losing precision.
+    /// Binary data, GUIDs, property keys and file times are strings formatted like in
+    /// [`Display`](core::fmt::Display), `Empty` and `Null` are `null`.
+    #[cfg(feature = "json")]
+    pub fn to_json_value(&self) -> serde_json::Value {
+        use serde_json::Value;
+        use DevProperty as P;
+
+        /// The largest integer such that it and all the smaller ones are exactly representable by a `f64`
+        const MAX_SAFE_INTEGER: u64 = (1 << f64::MANTISSA_DIGITS) - 1;
+
+        let u64json = |v: u64| {
+            if v <= MAX_SAFE_INTEGER {
+                Value::from(v)
+            } else {
+                Value::from(v.to_string())
+            }
+        };
+        let i64json = |v: i64| {
+            if v.unsigned_abs() <= MAX_SAFE_INTEGER {
+                Value::from(v)
+            } else {
+                Value::from(v.to_string())
+            }
+        };
+
+        match self {
+            P::Empty | P::Null => Value::Null,
+            P::Bool(v) => Value::from(*v),
+            P::BoolArray(v) => Value::from(v.clone()),
+            P::String(v) | P::IndirectString(v) => Value::from(v.as_str()),
+            P::StringList(v) => Value::from(v.clone()),
+            P::I8(v) => Value::from(*v),
+            P::I8Array(v) => Value::from(v.clone()),
+            P::U8(v) => Value::from(*v),
+            P::U8Array(v) => Value::from(v.clone()),
+            P::I16(v) => Value::from(*v),
+            P::I16Array(v) => Value::from(v.clone()),
+            P::U16(v) => Value::from(*v),
+            P::U16Array(v) => Value::from(v.clone()),
+            P::I32(v) => Value::from(*v),
+            P::I32Array(v) => Value::from(v.clone()),
+            P::U32(v) => Value::from(*v),
+            P::U32Array(v) => Value::from(v.clone()),
+            P::I64(v) => i64json(*v),
+            P::I64Array(v) => v.iter().map(|&v| i64json(v)).collect(),
+            P::U64(v) => u64json(*v),
+            P::U64Array(v) => v.iter().map(|&v| u64json(v)).collect(),
+            P::F32(v) => Value::from(*v),
+            P::F32Array(v) => Value::from(v.clone()),
+            P::F64(v) => Value::from(*v),
+            P::F64Array(v) => Value::from(v.clone()),
+            P::GuidArray(v) => v.iter().map(GuidWrap::to_string).collect(),
+            P::DevPropKeyArray(v) => v.iter().map(DevPropKey::to_string).collect(),
+            P::Binary(_)
+            | P::Guid(_)
+            | P::FileTime(_)
+            | P::Decimal(_)
+            | P::Currency(_)
+            | P::Date(_)
+            | P::PropType(_)
+            | P::SecurityDescriptor(_)
+            | P::DevPropKey(_)
+            | P::Unsupported { .. } => Value::from(self.to_string()),
+        }
+    }
+
+    /// Returns the number of elements of an array or list, the number of bytes of a `Binary`
+    /// or a `SecurityDescriptor`, and the number of UTF-16 code units of a string
+    ///
+    /// `None` is returned for scalar values
+    pub fn len(&self) -> Option<usize> {
+        use DevProperty as P;
+
+        Some(match self {
+            P::String(v) | P::IndirectString(v) => v.encode_utf16().count(),
+            P::StringList(v) => v.len(),
+            P::BoolArray(v) => v.len(),
+            P::I8Array(v) => v.len(),
+            P::U8Array(v) | P::Binary(v) | P::SecurityDescriptor(v) => v.len(),
+            P::I16Array(v) => v.len(),
+            P::U16Array(v) => v.len(),
+            P::I32Array(v) => v.len(),
+            P::U32Array(v) => v.len(),
+            P::I64Array(v) => v.len(),
+            P::U64Array(v) => v.len(),
+            P::F32Array(v) => v.len(),
+            P::F64Array(v) => v.len(),
+            P::GuidArray(v) => v.len(),
+            P::DevPropKeyArray(v) => v.len(),
+            _ => return None,
+        })
+    }
+
+    /// Returns whether or not the value is an empty array, list or string, see [`Self::len()`]
+    ///
+    /// Scalar values are never empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
+
+    /// Returns the `DEVPROP_TYPE_*` type the value is decoded from, modifiers included
+    fn prop_type(&self) -> DEVPROPTYPE {
+        use DevProperty as P;
+
+        match *self {
+            P::Empty => DEVPROP_TYPE_EMPTY,
+            P::Null => DEVPROP_TYPE_NULL,
+            P::Bool(_) => DEVPROP_TYPE_BOOLEAN,
+            P::BoolArray(_) => DEVPROP_TYPE_BOOLEAN | DEVPROP_TYPEMOD_ARRAY,
+            P::String(_) => DEVPROP_TYPE_STRING,
+            P::IndirectString(_) => DEVPROP_TYPE_STRING_INDIRECT,
+            P::StringList(_) => DEVPROP_TYPE_STRING | DEVPROP_TYPEMOD_LIST,
+            P::I8(_) => DEVPROP_TYPE_SBYTE,
+            P::I8Array(_) => DEVPROP_TYPE_SBYTE | DEVPROP_TYPEMOD_ARRAY,
+            P::U8(_) => DEVPROP_TYPE_BYTE,
+            P::U8Array(_) | P::Binary(_) => DEVPROP_TYPE_BYTE | DEVPROP_TYPEMOD_ARRAY,
+            P::I16(_) => DEVPROP_TYPE_INT16,
+            P::I16Array(_) => DEVPROP_TYPE_INT16 | DEVPROP_TYPEMOD_ARRAY,
+            P::U16(_) => DEVPROP_TYPE_UINT16,
+            P::U16Array(_) => DEVPROP_TYPE_UINT16 | DEVPROP_TYPEMOD_ARRAY,
+            P::I32(_) => DEVPROP_TYPE_INT32,
+            P::I32Array(_) => DEVPROP_TYPE_INT32 | DEVPROP_TYPEMOD_ARRAY,
+            P::U32(_) => DEVPROP_TYPE_UINT32,
+            P::U32Array(_) => DEVPROP_TYPE_UINT32 | DEVPROP_TYPEMOD_ARRAY,
+            P::I64(_) => DEVPROP_TYPE_INT64,
+            P::I64Array(_) => DEVPROP_TYPE_INT64 | DEVPROP_TYPEMOD_ARRAY,
+            P::U64(_) => DEVPROP_TYPE_UINT64,
+            P::U64Array(_) => DEVPROP_TYPE_UINT64 | DEVPROP_TYPEMOD_ARRAY,
+            P::F32(_) => DEVPROP_TYPE_FLOAT,
+            P::F32Array(_) => DEVPROP_TYPE_FLOAT | DEVPROP_TYPEMOD_ARRAY,
+            P::F64(_) => DEVPROP_TYPE_DOUBLE,
+            P::F64Array(_) => DEVPROP_TYPE_DOUBLE | DEVPROP_TYPEMOD_ARRAY,
+            P::Guid(_) => DEVPROP_TYPE_GUID,
+            P::GuidArray(_) => DEVPROP_TYPE_GUID | DEVPROP_TYPEMOD_ARRAY,
+            P::FileTime(_) => DEVPROP_TYPE_FILETIME,
+            P::Decimal(_) => DEVPROP_TYPE_DECIMAL,
+            P::Currency(_) => DEVPROP_TYPE_CURRENCY,
+            P::Date(_) => DEVPROP_TYPE_DATE,
+            P::PropType(_) => DEVPROP_TYPE_DEVPROPTYPE,
+            P::SecurityDescriptor(_) => DEVPROP_TYPE_SECURITY_DESCRIPTOR,
+            P::DevPropKey(_) => DEVPROP_TYPE_DEVPROPKEY,
+            P::DevPropKeyArray(_) => DEVPROP_TYPE_DEVPROPKEY | DEVPROP_TYPEMOD_ARRAY,
+            P::Unsupported { ty, .. } => ty,
+        }
+    }
+
+    /// Returns the `DEVPROP_TYPE_*` type of the value (or of its elements, for arrays and lists),
+    /// without the `DEVPROP_TYPEMOD_*` modifiers
+    ///
+    /// `Unsupported` values return the type they were decoded from, as it is
+    pub fn base_type(&self) -> DEVPROPTYPE {
+        match *self {
+            DevProperty::Unsupported { ty, .. } => ty,
+            _ => self.prop_type() & DEVPROP_MASK_TYPE,
+        }
+    }
+
+    /// Returns whether or not the value is an array (`Binary` included, as it's an array of bytes)
+    pub fn is_array(&self) -> bool {
+        self.prop_type() & DEVPROP_MASK_TYPEMOD == DEVPROP_TYPEMOD_ARRAY
+    }
+
+    /// Returns a readable name of the type of the value, see [`devprop::type_name()`]
+    pub fn type_name(&self) -> &'static str {
+        devprop::type_name(self.prop_type())
+    }
+
+    /// Returns the name of the variant, e.g. `"U32"` for [`DevProperty::U32`]
+    pub fn variant_name(&self) -> &'static str {
+        use DevProperty as P;
+
+        match self {
+            P::Empty => "Empty",
+            P::Null => "Null",
+            P::Bool(_) => "Bool",
+            P::BoolArray(_) => "BoolArray",
+            P::String(_) => "String",
+            P::IndirectString(_) => "IndirectString",
+            P::StringList(_) => "StringList",
+            P::I8(_) => "I8",
+            P::I8Array(_) => "I8Array",
+            P::U8(_) => "U8",
+            P::U8Array(_) => "U8Array",
+            P::I16(_) => "I16",
+            P::I16Array(_) => "I16Array",
+            P::U16(_) => "U16",
+            P::U16Array(_) => "U16Array",
+            P::I32(_) => "I32",
+            P::I32Array(_) => "I32Array",
+            P::U32(_) => "U32",
+            P::U32Array(_) => "U32Array",
+            P::I64(_) => "I64",
+            P::I64Array(_) => "I64Array",
+            P::U64(_) => "U64",
+            P::U64Array(_) => "U64Array",
+            P::F32(_) => "F32",
+            P::F32Array(_) => "F32Array",
+            P::F64(_) => "F64",
+            P::F64Array(_) => "F64Array",
+            P::Binary(_) => "Binary",
+            P::Guid(_) => "Guid",
+            P::GuidArray(_) => "GuidArray",
+            P::FileTime(_) => "FileTime",
+            P::Decimal(_) => "Decimal",
+            P::Currency(_) => "Currency",
+            P::Date(_) => "Date",
+            P::PropType(_) => "PropType",
+            P::SecurityDescriptor(_) => "SecurityDescriptor",
+            P::DevPropKey(_) => "DevPropKey",
+            P::DevPropKeyArray(_) => "DevPropKeyArray",
+            P::Unsupported { .. } => "Unsupported",
+        }
+    }
+}
+
+/// The number of `FILETIME` intervals in a second
+pub(crate) const FILETIME_TICKS_PER_SEC: u64 = 10_000_000;
+
+/// The number of days between the `FILETIME` epoch (1601-01-01) and the UNIX one (1970-01-01)
+pub(crate) const FILETIME_UNIX_EPOCH_DAYS: i64 = 134_774;
+
+/// The number of days between the OLE Automation `DATE` epoch (1899-12-30) and the UNIX one
+const OLE_DATE_UNIX_EPOCH_DAYS: f64 = 25_569.0;
+
+/// The factor an OLE Automation `CURRENCY` is scaled by
+const CURRENCY_SCALE: u64 = 10_000;
+
+/// Converts an OLE Automation `DATE` into the number of seconds since the UNIX epoch
+///
+/// The integer part of a `DATE` is the day (negative before the epoch) while the fractional
+/// part is always the time of that day, regardless of the sign (e.g. `-1.25` is 1899-12-29 06:00)
+pub(crate) fn ole_date_to_unix_secs(date: f64) -> f64 {
+    let days = trunc(date);
+    (days - OLE_DATE_UNIX_EPOCH_DAYS + (date - days).abs()) * 86400.0
+}
+
+/// The smallest `f64` that can't have a fractional part (2^52)
+const F64_INTEGER_THRESHOLD: f64 = 4_503_599_627_370_496.0;
+
+/// Same as `f64::trunc()`, which is only available with `std`
+fn trunc(v: f64) -> f64 {
+    // NOTE: bigger values are already integers (NaN and the infinities are kept as they are too)
+    match v.abs() < F64_INTEGER_THRESHOLD {
+        true => v as i64 as f64,
+        false => v,
+    }
+}
+
+/// Same as `f64::round()`, which is only available with `std`
+fn round(v: f64) -> f64 {
+    let int = trunc(v);
+    // NOTE: the difference is exact, as `int` has the same magnitude of `v`
+    match (v - int).abs() >= 0.5 {
+        true if v < 0.0 => int - 1.0,
+        true => int + 1.0,
+        false => int,
+    }
+}
+
+/// Formats an OLE Automation `DECIMAL`, see [`DevProperty::Decimal`] for the layout
+fn decimal_to_string(raw: &[u8; 16]) -> String {
+    let scale = usize::from(raw[2]);
+    let negative = raw[3] & 0x80 != 0;
+    let hi = u32::from_ne_bytes(raw[4..8].try_into().unwrap());
+    let lo = u64::from_ne_bytes(raw[8..16].try_into().unwrap());
+    let digits = (u128::from(hi) << 64 | u128::from(lo)).to_string();
+    // NOTE: there must be at least one digit before the point
+    let digits = format!("{digits:0>width$}", width = scale + 1);
+    let (int, frac) = digits.split_at(digits.len() - scale);
+    let sign = if negative { "-" } else { "" };
+    match frac {
+        "" => format!("{sign}{int}"),
+        _ => format!("{sign}{int}.{frac}"),
+    }
+}
+
+/// Parses a `DECIMAL` formatted by [`decimal_to_string()`], `None` if the string isn't valid
+/// or the value doesn't fit
+#[cfg(feature = "serde")]
+fn decimal_from_str(s: &str) -> Option<[u8; 16]> {
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(s) => (true, s),
+        None => (false, s),
+    };
+    let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+    // NOTE: a `DECIMAL` has at most 28 digits after the point
+    if int.is_empty()
+        || frac.len() > 28
+        || !(int.bytes().chain(frac.bytes())).all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    let value: u128 = format!("{int}{frac}").parse().ok()?;
+    if value >> 96 != 0 {
+        return None;
+    }
+    let mut raw = [0; 16];
+    raw[2] = frac.len() as u8;
+    raw[3] = if negative { 0x80 } else { 0 };
+    raw[4..8].copy_from_slice(&((value >> 64) as u32).to_ne_bytes());
+    raw[8..16].copy_from_slice(&(value as u64).to_ne_bytes());
+    Some(raw)
+}
+
+/// Converts a number of days since the UNIX epoch into a `(year, month, day)` date
+// NOTE: this is Howard Hinnant's `civil_from_days` algorithm
+// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    (yoe + era * 400 + i64::from(m <= 2), m, d)
+}
+
+/// The alphabet of the standard base64 encoding
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes the bytes as standard base64, with padding
+fn base64_encode(v: &[u8]) -> String {
+    let mut encoded = String::with_capacity(v.len().div_ceil(3) * 4);
+    for chunk in v.chunks(3) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |bits, (i, &v)| bits | u32::from(v) << (16 - 8 * i));
+        // NOTE: n bytes are encoded by n + 1 digits, the rest of the group is padding
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => encoded.push(char::from(
+                    BASE64_ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize],
+                )),
+                false => encoded.push('='),
+            }
+        }
+    }
+    encoded
+}
+
+/// Decodes standard base64, with padding, `None` if the string isn't valid
+fn base64_decode(encoded: &str) -> Option<Vec<u8>> {
+    let encoded = encoded.as_bytes();
+    if !encoded.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut decoded = Vec::with_capacity(encoded.len() / 4 * 3);
+    for (i, group) in encoded.chunks(4).enumerate() {
+        let padding = group.iter().rev().take_while(|&&c| c == b'=').count();
+        // NOTE: the padding is only allowed at the end, and at most 2 characters
+        if padding > 2 || (padding > 0 && (i + 1) * 4 != encoded.len()) {
+            return None;
+        }
+        let mut bits = 0u32;
+        for (j, &c) in group[..4 - padding].iter().enumerate() {
+            let digit = BASE64_ALPHABET.iter().position(|&v| v == c)?;
+            bits |= (digit as u32) << (18 - 6 * j);
+        }
+        decoded.extend_from_slice(&bits.to_be_bytes()[1..4 - padding]);
+    }
+    Some(decoded)
+}
+
+/// Decodes a hex string (in either case), `None` if the string isn't valid
+#[cfg(feature = "serde")]
+fn hex_decode(encoded: &str) -> Option<Vec<u8>> {
+    if !encoded.len().is_multiple_of(2) || !encoded.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    // NOTE: all the characters are ASCII digits, so the string can be split at any position
+    (0..encoded.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&encoded[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Writes the integer in decimal, or in zero-padded hex when the alternate flag (`{:#}`) is set
+fn fmt_int<T>(f: &mut core::fmt::Formatter<'_>, v: &T) -> core::fmt::Result
+where
+    T: core::fmt::Display + core::fmt::LowerHex,
+{
+    if f.alternate() {
+        let width = size_of::<T>() * 2;
+        write!(f, "0x{v:0width$x}")
+    } else {
+        write!(f, "{v}")
+    }
+}
+
+/// Writes the integers like a list, each one formatted as by [`fmt_int()`],
+/// or one per line when the alternate flag (`{:#}`) is set
+fn fmt_int_array<T>(f: &mut core::fmt::Formatter<'_>, v: &[T]) -> core::fmt::Result
+where
+    T: core::fmt::Display + core::fmt::LowerHex,
+{
+    if f.alternate() {
+        return fmt_lines(f, v, fmt_int);
+    }
+    write!(f, "[")?;
+    for (i, v) in v.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        fmt_int(f, v)?;
+    }
+    write!(f, "]")
+}
+
+/// Writes the items one per line, each one formatted by the given function
+fn fmt_lines<T>(
+    f: &mut core::fmt::Formatter<'_>,
+    v: &[T],
+    mut item: impl FnMut(&mut core::fmt::Formatter<'_>, &T) -> core::fmt::Result,
+) -> core::fmt::Result {
+    for (i, v) in v.iter().enumerate() {
+        if i > 0 {
+            writeln!(f)?;
+        }
+        item(f, v)?;
+    }
+    Ok(())
+}
+
+/// Writes the bytes as contiguous lowercase hex, or as space separated uppercase hex
+/// when the alternate flag (`{:#}`) is set
+///
+/// With the alternate flag the precision, if given, is the number of bytes per row
+/// (e.g. `{:#.16}` writes rows of 16 bytes)
+fn fmt_binary(f: &mut core::fmt::Formatter<'_>, v: &[u8]) -> core::fmt::Result {
+    if !f.alternate() {
+        return v.iter().try_for_each(|v| write!(f, "{v:02x}"));
+    }
+    let row = f
+        .precision()
+        .filter(|&row| row > 0)
+        .unwrap_or(v.len().max(1));
+    for (i, line) in v.chunks(row).enumerate() {
+        if i > 0 {
+            writeln!(f)?;
+        }
+        for (j, v) in line.iter().enumerate() {
+            if j > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{v:02X}")?;
+        }
+    }
+    Ok(())
+}
+
+/// Integers (and integer arrays) are written in hex with the alternate flag (`{:#}`),
+/// binary data is written as space separated uppercase bytes, in rows of `precision` bytes if given,
+/// and numeric arrays, GUID arrays and string lists are written one item per line
+impl core::fmt::Display for DevProperty {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DevProperty::Empty => write!(f, "#EMPTY"),
+            DevProperty::Null => write!(f, "#NULL"),
+            DevProperty::Bool(v) => write!(f, "{v}"),
+            DevProperty::BoolArray(v) => write!(f, "{v:?}"),
+            DevProperty::String(v) | DevProperty::IndirectString(v) => write!(f, "{v}"),
+            DevProperty::StringList(v) if f.alternate() => fmt_lines(f, v, |f, v| write!(f, "{v}")),
+            DevProperty::StringList(v) => write!(f, "{}", v.join("; ")),
+            DevProperty::I8(v) => fmt_int(f, v),
+            DevProperty::I8Array(v) => fmt_int_array(f, v),
+            DevProperty::U8(v) => fmt_int(f, v),
+            DevProperty::U8Array(v) => fmt_int_array(f, v),
+            DevProperty::I16(v) => fmt_int(f, v),
+            DevProperty::I16Array(v) => fmt_int_array(f, v),
+            DevProperty::U16(v) => fmt_int(f, v),
+            DevProperty::U16Array(v) => fmt_int_array(f, v),
+            DevProperty::I32(v) => fmt_int(f, v),
+            DevProperty::I32Array(v) => fmt_int_array(f, v),
+            DevProperty::U32(v) => fmt_int(f, v),
+            DevProperty::U32Array(v) => fmt_int_array(f, v),
+            DevProperty::I64(v) => fmt_int(f, v),
+            DevProperty::I64Array(v) => fmt_int_array(f, v),
+            DevProperty::U64(v) => fmt_int(f, v),
+            DevProperty::U64Array(v) => fmt_int_array(f, v),
+            DevProperty::F32(v) => write!(f, "{v}"),
+            DevProperty::F32Array(v) if f.alternate() => fmt_lines(f, v, |f, v| write!(f, "{v}")),
+            DevProperty::F32Array(v) => write!(f, "{v:?}"),
+            DevProperty::F64(v) => write!(f, "{v}"),
+            DevProperty::F64Array(v) if f.alternate() => fmt_lines(f, v, |f, v| write!(f, "{v}")),
+            DevProperty::F64Array(v) => write!(f, "{v:?}"),
+            DevProperty::Binary(v) => fmt_binary(f, v),
+            DevProperty::Guid(v) => write!(f, "{v}"),
+            DevProperty::GuidArray(v) if f.alternate() => fmt_lines(f, v, |f, v| write!(f, "{v}")),
+            DevProperty::GuidArray(v) => write!(f, "{v:?}"),
+            DevProperty::FileTime(v) => {
+                let (days, secs) = (
+                    v / FILETIME_TICKS_PER_SEC / 86400,
+                    v / FILETIME_TICKS_PER_SEC % 86400,
+                );
+                let (y, m, d) = civil_from_days(days as i64 - FILETIME_UNIX_EPOCH_DAYS);
+                let (hh, mm, ss) = (secs / 3600, secs / 60 % 60, secs % 60);
+                write!(f, "{y:04}-{m:02}-{d:02}T{hh:02}:{mm:02}:{ss:02}Z")
+            }
+            DevProperty::Decimal(v) => write!(f, "{}", decimal_to_string(v)),
+            DevProperty::Currency(v) => {
+                let sign = if *v < 0 { "-" } else { "" };
+                let v = v.unsigned_abs();
+                write!(f, "{sign}{}.{:04}", v / CURRENCY_SCALE, v % CURRENCY_SCALE)
+            }
+            DevProperty::Date(v) => {
+                let secs = round(ole_date_to_unix_secs(*v)) as i64;
+                let (days, secs) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+                let (y, m, d) = civil_from_days(days);
+                let (hh, mm, ss) = (secs / 3600, secs / 60 % 60, secs % 60);
+                write!(f, "{y:04}-{m:02}-{d:02}T{hh:02}:{mm:02}:{ss:02}Z")
+            }
+            DevProperty::SecurityDescriptor(v) => v.iter().try_for_each(|v| write!(f, "{v:02x}")),
+            DevProperty::DevPropKey(v) => write!(f, "{v}"),
+            DevProperty::DevPropKeyArray(v) => {
+                let keys: Vec<_> = v.iter().map(DevPropKey::to_string).collect();
+                write!(f, "{keys:?}")
+            }
+            DevProperty::PropType(v) => write!(f, "{}", devprop::type_name(*v)),
+            DevProperty::Unsupported { ty, data } => {
+                write!(f, "#UNSUP{{{ty}}}[{} bytes]", data.len())
+            }
+        }
+    }
+}
+
+/// Serializes the value as a tagged object, like `{"type": "U32", "value": 5}`
+///
+/// GUIDs and property keys are serialized as their [`Display`](core::fmt::Display) strings,
+/// binary data and security descriptors as lowercase hex strings,
+/// `Decimal` as its [`Display`](core::fmt::Display) string, `FileTime`, `Currency` and `Date`
+/// as their raw numbers, `PropType` as the type name and `Unsupported` as a pair
+/// of the raw type and the hex string of its data
+///
+/// Wrap the value in a [`Base64Binary`] to serialize binary data as base64 instead
+#[cfg(feature = "serde")]
+impl serde::Serialize for DevProperty {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_property(self, serializer, false)
+    }
+}
+
+/// Serializes the wrapped value like [`DevProperty`] does, but with `Binary` values
+/// as base64 strings (see [`DevProperty::binary_base64()`]) instead of hex
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, Copy)]
+pub struct Base64Binary<'a>(pub &'a DevProperty);
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Base64Binary<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_property(self.0, serializer, true)
+    }
+}
+
+/// Deserializes the tagged object written by the `Serialize` implementation, with the
+/// `"type"` field before the `"value"` one
+///
+/// `Binary` values must be hex strings, `PropType` ones must have a known type name and
+/// `Unsupported` ones must be a pair of the raw type and a hex string
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for DevProperty {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("DevProperty", &["type", "value"], PropertyVisitor)
+    }
+}
+
+/// The visitor of the tagged object of a serialized [`DevProperty`]
+#[cfg(feature = "serde")]
+struct PropertyVisitor;
+
+#[cfg(feature = "serde")]
+impl<'de> serde::de::Visitor<'de> for PropertyVisitor {
+    type Value = DevProperty;
+
+    fn expecting(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "an object with a \"type\" and a \"value\" field")
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<DevProperty, A::Error> {
+        use serde::de::{Error, IgnoredAny};
+
+        let mut ty: Option<String> = None;
+        let mut value = None;
+        while let Some(field) = map.next_key::<String>()? {
+            match (field.as_str(), &ty) {
+                ("type", _) => ty = Some(map.next_value()?),
+                ("value", Some(ty)) => value = Some(deserialize_value(ty, &mut map)?),
+                ("value", None) => return Err(A::Error::custom("\"type\" must precede \"value\"")),
+                _ => drop(map.next_value::<IgnoredAny>()?),
+            }
+        }
+        value.ok_or_else(|| A::Error::missing_field("value"))
+    }
+}
+
+/// Reads the `"value"` field of a serialized [`DevProperty`] of the variant named `ty`
+#[cfg(feature = "serde")]
+fn deserialize_value<'de, A: serde::de::MapAccess<'de>>(
+    ty: &str,
+    map: &mut A,
+) -> Result<DevProperty, A::Error> {
+    use serde::de::Error;
+    use DevProperty as P;
+
+    let invalid = |what: &str| A::Error::custom(format!("invalid {what} in a {ty} value"));
+    let guid = |v: &str| v.parse::<GuidWrap>().map_err(A::Error::custom);
+    let key = |v: &str| DevPropKey::parse(v).ok_or_else(|| invalid("property key"));
+
+    Ok(match ty {
+        "Empty" => map.next_value::<()>().map(|_| P::Empty)?,
+        "Null" => map.next_value::<()>().map(|_| P::Null)?,
+        "Bool" => P::Bool(map.next_value()?),
+        "BoolArray" => P::BoolArray(map.next_value()?),
+        "String" => P::String(map.next_value()?),
+        "IndirectString" => P::IndirectString(map.next_value()?),
+        "StringList" => P::StringList(map.next_value()?),
+        "I8" => P::I8(map.next_value()?),
+        "I8Array" => P::I8Array(map.next_value()?),
+        "U8" => P::U8(map.next_value()?),
+        "U8Array" => P::U8Array(map.next_value()?),
+        "I16" => P::I16(map.next_value()?),
+        "I16Array" => P::I16Array(map.next_value()?),
+        "U16" => P::U16(map.next_value()?),
+        "U16Array" => P::U16Array(map.next_value()?),
+        "I32" => P::I32(map.next_value()?),
+        "I32Array" => P::I32Array(map.next_value()?),
+        "U32" => P::U32(map.next_value()?),
+        "U32Array" => P::U32Array(map.next_value()?),
+        "I64" => P::I64(map.next_value()?),
+        "I64Array" => P::I64Array(map.next_value()?),
+        "U64" => P::U64(map.next_value()?),
+        "U64Array" => P::U64Array(map.next_value()?),
+        "F32" => P::F32(map.next_value()?),
+        "F32Array" => P::F32Array(map.next_value()?),
+        "F64" => P::F64(map.next_value()?),
+        "F64Array" => P::F64Array(map.next_value()?),
+        "Binary" => {
+            P::Binary(hex_decode(&map.next_value::<String>()?).ok_or_else(|| invalid("hex"))?)
+        }
+        "Guid" => P::Guid(guid(&map.next_value::<String>()?)?),
+        "GuidArray" => P::GuidArray(
+            map.next_value::<Vec<String>>()?
+                .iter()
+                .map(|v| guid(v))
+                .collect::<Result<_, _>>()?,
+        ),
+        "FileTime" => P::FileTime(map.next_value()?),
+        "Decimal" => P::Decimal(
+            decimal_from_str(&map.next_value::<String>()?).ok_or_else(|| invalid("decimal"))?,
+        ),
+        "Currency" => P::Currency(map.next_value()?),
+        "Date" => P::Date(map.next_value()?),
+        "PropType" => P::PropType(
+            devprop::type_from_name(&map.next_value::<String>()?)
+                .ok_or_else(|| invalid("type name"))?,
+        ),
+        "SecurityDescriptor" => P::SecurityDescriptor(
+            hex_decode(&map.next_value::<String>()?).ok_or_else(|| invalid("hex"))?,
+        ),
+        "DevPropKey" => P::DevPropKey(key(&map.next_value::<String>()?)?),
+        "DevPropKeyArray" => P::DevPropKeyArray(
+            map.next_value::<Vec<String>>()?
+                .iter()
+                .map(|v| key(v))
+                .collect::<Result<_, _>>()?,
+        ),
+        "Unsupported" => {
+            let (ty, data) = map.next_value::<(DEVPROPTYPE, String)>()?;
+            P::Unsupported {
+                ty,
+                data: hex_decode(&data).ok_or_else(|| invalid("hex"))?.into(),
+            }
+        }
+        _ => return Err(A::Error::custom(format!("unknown property type {ty:?}"))),
+    })
+}
+
+/// Serializes the value as described in the `Serialize` implementation of [`DevProperty`],
+/// with `Binary` values as base64 instead of hex if `base64` is set
+#[cfg(feature = "serde")]
+fn serialize_property<S: serde::Serializer>(
+    prop: &DevProperty,
+    serializer: S,
+    base64: bool,
+) -> Result<S::Ok, S::Error> {
+    use serde::ser::SerializeStruct;
+    use DevProperty as P;
+
+    let hex = |v: &[u8]| -> String { v.iter().map(|v| format!("{v:02x}")).collect() };
+
+    let mut state = serializer.serialize_struct("DevProperty", 2)?;
+    state.serialize_field("type", prop.variant_name())?;
+    match prop {
+        P::Empty => state.serialize_field("value", &())?,
+        P::Null => state.serialize_field("value", &())?,
+        P::Bool(v) => state.serialize_field("value", v)?,
+        P::BoolArray(v) => state.serialize_field("value", v)?,
+        P::String(v) | P::IndirectString(v) => state.serialize_field("value", v)?,
+        P::StringList(v) => state.serialize_field("value", v)?,
+        P::I8(v) => state.serialize_field("value", v)?,
+        P::I8Array(v) => state.serialize_field("value", v)?,
+        P::U8(v) => state.serialize_field("value", v)?,
+        P::U8Array(v) => state.serialize_field("value", v)?,
+        P::I16(v) => state.serialize_field("value", v)?,
+        P::I16Array(v) => state.serialize_field("value", v)?,
+        P::U16(v) => state.serialize_field("value", v)?,
+        P::U16Array(v) => state.serialize_field("value", v)?,
+        P::I32(v) => state.serialize_field("value", v)?,
+        P::I32Array(v) => state.serialize_field("value", v)?,
+        P::U32(v) => state.serialize_field("value", v)?,
+        P::U32Array(v) => state.serialize_field("value", v)?,
+        P::I64(v) => state.serialize_field("value", v)?,
+        P::I64Array(v) => state.serialize_field("value", v)?,
+        P::U64(v) => state.serialize_field("value", v)?,
+        P::U64Array(v) => state.serialize_field("value", v)?,
+        P::F32(v) => state.serialize_field("value", v)?,
+        P::F32Array(v) => state.serialize_field("value", v)?,
+        P::F64(v) => state.serialize_field("value", v)?,
+        P::F64Array(v) => state.serialize_field("value", v)?,
+        P::Binary(v) if base64 => state.serialize_field("value", &base64_encode(v))?,
+        P::Binary(v) => state.serialize_field("value", &hex(v))?,
+        P::Guid(v) => state.serialize_field("value", &v.to_string())?,
+        P::GuidArray(v) => {
+            let guids: Vec<_> = v.iter().map(GuidWrap::to_string).collect();
+            state.serialize_field("value", &guids)?
+        }
+        P::FileTime(v) => state.serialize_field("value", v)?,
+        P::Decimal(v) => state.serialize_field("value", &decimal_to_string(v))?,
+        P::Currency(v) => state.serialize_field("value", v)?,
+        P::Date(v) => state.serialize_field("value", v)?,
+        P::PropType(v) => state.serialize_field("value", devprop::type_name(*v))?,
+        P::SecurityDescriptor(v) => state.serialize_field("value", &hex(v))?,
+        P::DevPropKey(v) => state.serialize_field("value", &v.to_string())?,
+        P::DevPropKeyArray(v) => {
+            let keys: Vec<_> = v.iter().map(DevPropKey::to_string).collect();
+            state.serialize_field("value", &keys)?
+        }
+        P::Unsupported { ty, data } => state.serialize_field("value", &(ty, hex(data)))?,
+    }
+    state.end()
+}
+
+#[derive(Clone, Copy)]
+pub struct GuidWrap(pub GUID);
+
+impl GuidWrap {
+    /// Returns the fields of the GUID as a tuple, used to hash and order it
+    fn fields(&self) -> (u32, u16, u16, [u8; 8]) {
+        let GUID {
+            Data1,
+            Data2,
+            Data3,
+            Data4,
+        } = self.0;
+        (Data1, Data2, Data3, Data4)
+    }
+
+    /// Returns the GUID in its canonical lowercase hyphenated form, without braces,
+    /// the same as its [`Display`](core::fmt::Display)
+    pub fn to_canonical_string(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// GUIDs are compared field by field
+impl PartialEq for GuidWrap {
+    fn eq(&self, other: &Self) -> bool {
+        IsEqualGUID(&self.0, &other.0)
+    }
+}
+
+impl Eq for GuidWrap {}
+
+impl core::hash::Hash for GuidWrap {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.fields().hash(state);
+    }
+}
+
+impl PartialOrd for GuidWrap {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// GUIDs are ordered field by field, which matches the order of their hyphenated form
+impl Ord for GuidWrap {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.fields().cmp(&other.fields())
+    }
+}
+
+impl core::fmt::Debug for GuidWrap {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Guid")
+            .field("Data1", &self.0.Data1)
+            .field("Data2", &self.0.Data2)
+            .field("Data3", &self.0.Data3)
+            .field("Data4", &self.0.Data4)
+            .finish()
+    }
+}
+
+/// Formats the GUID in its lowercase hyphenated form,
+/// the alternate flag (`{:#}`) wraps it in braces
+impl core::fmt::Display for GuidWrap {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let GUID {
+            Data1: a,
+            Data2: b,
+            Data3: c,
+            Data4: [d, e, f, g, h, i, j, k],
+        } = self.0;
+        let (open, close) = if fmt.alternate() {
+            ("{", "}")
+        } else {
+            ("", "")
+        };
+        write!(
+            fmt,
+            "{open}{a:08x}-{b:04x}-{c:04x}-{d:02x}{e:02x}-{f:02x}{g:02x}{h:02x}{i:02x}{j:02x}{k:02x}{close}"
+        )
+    }
+}
+
+/// Formats the GUID in its uppercase hyphenated form (`{:X}`), as used by the registry,
+/// the alternate flag (`{:#X}`) wraps it in braces
+impl core::fmt::UpperHex for GuidWrap {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let GUID {
+            Data1: a,
+            Data2: b,
+            Data3: c,
+            Data4: [d, e, f, g, h, i, j, k],
+        } = self.0;
+        let (open, close) = if fmt.alternate() {
+            ("{", "}")
+        } else {
+            ("", "")
+        };
+        write!(
+            fmt,
+            "{open}{a:08X}-{b:04X}-{c:04X}-{d:02X}{e:02X}-{f:02X}{g:02X}{h:02X}{i:02X}{j:02X}{k:02X}{close}"
+        )
+    }
+}
+
+/// Parses a GUID in its hyphenated form, with or without the surrounding braces
+/// (e.g. `{53f56307-b6bf-11d0-94f2-00a0c91efb8b}` or `53f56307-b6bf-11d0-94f2-00a0c91efb8b`)
+impl core::str::FromStr for GuidWrap {
+    type Err = GuidParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let inner = match (s.strip_prefix('{'), s.strip_suffix('}')) {
+            (Some(_), Some(_)) => &s[1..s.len() - 1],
+            (None, None) => s,
+            _ => return Err(GuidParseError::UnmatchedBrace),
+        };
+        if inner.len() != 36 {
+            return Err(GuidParseError::InvalidLength(inner.len()));
+        }
+        for (i, c) in inner.bytes().enumerate() {
+            match (matches!(i, 8 | 13 | 18 | 23), c) {
+                (true, b'-') => (),
+                (true, _) => return Err(GuidParseError::InvalidSeparator(i)),
+                (false, c) if c.is_ascii_hexdigit() => (),
+                (false, _) => return Err(GuidParseError::InvalidDigit(i)),
+            }
+        }
+        // NOTE: all the digits have been validated above, so the conversions can't fail
+        let u8at = |i: usize| u8::from_str_radix(&inner[i..i + 2], 16).unwrap();
+        Ok(Self(GUID {
+            Data1: u32::from_str_radix(&inner[0..8], 16).unwrap(),
+            Data2: u16::from_str_radix(&inner[9..13], 16).unwrap(),
+            Data3: u16::from_str_radix(&inner[14..18], 16).unwrap(),
+            Data4: [19, 21, 24, 26, 28, 30, 32, 34].map(u8at),
+        }))
+    }
+}
+
+/// The error returned when parsing a [`GuidWrap`] fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuidParseError {
+    /// Only one of the opening and closing braces is present
+    UnmatchedBrace,
+    /// The GUID (without braces) isn't 36 characters long, the actual length is attached
+    InvalidLength(usize),
+    /// A hyphen was expected at the attached position
+    InvalidSeparator(usize),
+    /// A hexadecimal digit was expected at the attached position
+    InvalidDigit(usize),
+}
+
+impl core::fmt::Display for GuidParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            GuidParseError::UnmatchedBrace => write!(f, "unmatched brace"),
+            GuidParseError::InvalidLength(v) => write!(f, "expected 36 characters, found {v}"),
+            GuidParseError::InvalidSeparator(v) => write!(f, "expected '-' at position {v}"),
+            GuidParseError::InvalidDigit(v) => write!(f, "expected hex digit at position {v}"),
+        }
+    }
+}
+
+impl core::error::Error for GuidParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses a GUID written in its hyphenated form
+    fn guid(s: &str) -> GuidWrap {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn guid_wrap_dedups_a_guid_array() {
+        let a = guid("53f56307-b6bf-11d0-94f2-00a0c91efb8b");
+        let b = guid("{53F56307-B6BF-11D0-94F2-00A0C91EFB8B}");
+        let c = guid("53f5630d-b6bf-11d0-94f2-00a0c91efb8b");
+        assert_eq!(a, b);
+
+        let mut guids = vec![c, a, b, c];
+        guids.sort();
+        guids.dedup();
+        assert_eq!(guids, [a, c]);
+        assert_eq!(a.cmp(&b), core::cmp::Ordering::Equal);
+
+        let array = DevProperty::GuidArray(vec![a, c, b]);
+        assert!(array.guid_array_contains(&c.0));
+        assert!(!DevProperty::GuidArray(vec![a]).guid_array_contains(&c.0));
+    }
+
+    #[test]
+    fn guid_dedup_and_intersection() {
+        let a = guid("53f56307-b6bf-11d0-94f2-00a0c91efb8b");
+        let b = guid("53f5630d-b6bf-11d0-94f2-00a0c91efb8b");
+        let c = guid("6ac27878-a6fa-4155-ba85-f98f491d4f33");
+
+        let array = DevProperty::GuidArray(vec![b, a, b, c, a]);
+        let dedup: Vec<_> = array
+            .guid_dedup()
+            .unwrap()
+            .iter()
+            .map(|&v| GuidWrap(v))
+            .collect();
+        assert_eq!(dedup, [b, a, c]);
+
+        let other = DevProperty::GuidArray(vec![c, b, c]);
+        let common: Vec<_> = array
+            .guid_intersection(&other)
+            .unwrap()
+            .iter()
+            .map(|&v| GuidWrap(v))
+            .collect();
+        assert_eq!(common, [b, c]);
+
+        assert!(array.guid_intersection(&DevProperty::U32(0)).is_none());
+        assert!(DevProperty::U32(0).guid_dedup().is_none());
+    }
+
+    #[test]
+    fn hex_dump_rows() {
+        let binary = DevProperty::Binary(b"Hello, SD card!\x00\x01\x02\xff\x7f".to_vec());
+        let dump = binary.hex_dump().unwrap();
+        let rows: Vec<_> = dump.lines().collect();
+        assert_eq!(
+            rows,
+            [
+                "0000: 48 65 6c 6c 6f 2c 20 53 44 20 63 61 72 64 21 00  Hello, SD card!.",
+                &format!("0010: 01 02 ff 7f{}  ....", " ".repeat(36)),
+            ]
+        );
+        // NOTE: the ASCII gutters of the two rows are aligned
+        assert_eq!(rows[0].find("  Hello"), rows[1].find("  ...."));
+
+        assert_eq!(DevProperty::U32(0).hex_dump(), None);
+    }
+
+    #[test]
+    fn base64_round_trip() {
+        let binary = DevProperty::Binary(vec![0, 1, 2, 0x7f, 0x80, 0xfe, 0xff, b'S', b'D', 0]);
+        let encoded = binary.binary_base64().unwrap();
+        assert_eq!(encoded, "AAECf4D+/1NEAA==");
+        assert_eq!(DevProperty::binary_from_base64(&encoded), Some(binary));
+    }
+
+    #[test]
+    fn base64_padding() {
+        for (raw, encoded) in [(&b"Man"[..], "TWFu"), (b"Ma", "TWE="), (b"M", "TQ==")] {
+            let binary = DevProperty::Binary(raw.to_vec());
+            assert_eq!(binary.binary_base64().as_deref(), Some(encoded));
+            assert_eq!(DevProperty::binary_from_base64(encoded), Some(binary));
+        }
+        assert_eq!(DevProperty::binary_from_base64("TQ="), None);
+        assert_eq!(DevProperty::binary_from_base64("T==="), None);
+        assert_eq!(DevProperty::binary_from_base64("TQ==TWFu"), None);
+    }
+
+    #[test]
+    fn alternate_display_is_multiline() {
+        let values = [
+            DevProperty::U32Array(vec![1, 2, 3]),
+            DevProperty::I16Array(vec![-1, 2]),
+            DevProperty::F64Array(vec![1.5, -2.0]),
+            DevProperty::StringList(vec!["a".into(), "b".into(), "c".into()]),
+        ];
+        for value in values {
+            let len = value.len().unwrap();
+            assert!(!format!("{value}").contains('\n'), "{value}");
+            assert_eq!(format!("{value:#}").lines().count(), len, "{value:#}");
+        }
+        assert_eq!(
+            format!("{:#}", DevProperty::U16Array(vec![1, 0xab])),
+            "0x0001\n0x00ab"
+        );
+        assert_eq!(
+            format!("{}", DevProperty::U16Array(vec![1, 0xab])),
+            "[1, 171]"
+        );
+    }
+
+    #[test]
+    fn sort_numeric_values() {
+        let mut values = Vec::from([
+            DevProperty::U32(30),
+            DevProperty::U32(1),
+            DevProperty::U32(u32::MAX),
+            DevProperty::U32(7),
+        ]);
+        values.sort_by(|a, b| a.partial_cmp_numeric(b).unwrap());
+        let sorted: Vec<_> = values.iter().filter_map(DevProperty::as_u64).collect();
+        assert_eq!(sorted, [1, 7, 30, u64::from(u32::MAX)]);
+
+        let mut floats = Vec::from([
+            DevProperty::F64(1.0),
+            DevProperty::F64(f64::NAN),
+            DevProperty::F64(0.0),
+            DevProperty::F64(-0.0),
+            DevProperty::F64(-1.0),
+        ]);
+        floats.sort_by(|a, b| a.partial_cmp_numeric(b).unwrap());
+        let bits: Vec<_> = floats
+            .iter()
+            .map(|v| v.as_f64().unwrap().to_bits())
+            .collect();
+        let expected = [-1.0, -0.0, 0.0, 1.0, f64::NAN].map(f64::to_bits);
+        assert_eq!(bits, expected);
+
+        assert_eq!(
+            DevProperty::U32(1).partial_cmp_numeric(&DevProperty::U64(1)),
+            None
+        );
+    }
+
+    #[test]
+    fn try_from_matching_variant() {
+        assert_eq!(u32::try_from(DevProperty::U32(7)).unwrap(), 7);
+        assert!(bool::try_from(DevProperty::Bool(true)).unwrap());
+        assert_eq!(
+            Vec::<u32>::try_from(DevProperty::U32Array(vec![1, 2])).unwrap(),
+            [1, 2]
+        );
+        assert_eq!(
+            Vec::<u8>::try_from(DevProperty::Binary(vec![0xab])).unwrap(),
+            [0xab]
+        );
+        assert_eq!(
+            String::try_from(DevProperty::IndirectString("@a".into())).unwrap(),
+            "@a"
+        );
+
+        let a = guid("53f56307-b6bf-11d0-94f2-00a0c91efb8b");
+        assert_eq!(GuidWrap(GUID::try_from(DevProperty::Guid(a)).unwrap()), a);
+    }
+
+    #[test]
+    fn try_from_mismatched_variant() {
+        let err = u32::try_from(DevProperty::U64(7)).unwrap_err();
+        assert_eq!(err.expected, "U32");
+        assert_eq!(err.value, DevProperty::U64(7));
+        assert_eq!(err.to_string(), "expected U32, found U64");
+
+        let err = Vec::<u8>::try_from(DevProperty::U16Array(vec![1])).unwrap_err();
+        assert_eq!(err.expected, "U8Array | Binary");
+        assert_eq!(err.value, DevProperty::U16Array(vec![1]));
+
+        assert!(GUID::try_from(DevProperty::String("guid".into())).is_err());
+    }
+
+    /// A lone high surrogate between two letters
+    const LONE_SURROGATE: [u16; 3] = [0x61, 0xd800, 0x62];
+
+    #[test]
+    fn lone_surrogate_is_rejected_or_replaced() {
+        assert_eq!(DevProperty::try_from_utf16(&LONE_SURROGATE), None);
+
+        let lossy = DevProperty::from_utf16_lossy(&LONE_SURROGATE);
+        assert_eq!(lossy, DevProperty::String("a\u{fffd}b".into()));
+        assert_eq!(lossy.try_to_string().as_deref(), Some("a\u{fffd}b"));
+
+        let valid = DevProperty::try_from_utf16(&[0x61, 0xd83d, 0xde00]);
+        assert_eq!(valid, Some(DevProperty::String("a\u{1f600}".into())));
+    }
+
+    #[test]
+    fn empty_arrays_and_strings() {
+        assert!(DevProperty::U8Array(Vec::new()).is_empty());
+        assert!(!DevProperty::U8Array(vec![0]).is_empty());
+        assert!(DevProperty::String(String::new()).is_empty());
+        assert!(!DevProperty::String("SD".into()).is_empty());
+
+        let scalar = DevProperty::Guid(guid("53f56307-b6bf-11d0-94f2-00a0c91efb8b"));
+        assert!(!scalar.is_empty());
+        assert_eq!(scalar.len(), None);
+    }
+
+    #[test]
+    fn smart_display_of_byte_arrays() {
+        let text = DevProperty::U8Array(b"SD Card reader\0".to_vec());
+        assert_eq!(text.display_smart(), r#""SD Card reader\x00""#);
+        let text = DevProperty::I8Array(b"Generic".map(|v| v as i8).to_vec());
+        assert_eq!(text.display_smart(), r#""Generic""#);
+
+        let binary = DevProperty::Binary(vec![0, 0xab, b'S', 0xff]);
+        assert_eq!(binary.display_smart(), "00ab53ff");
+
+        assert_eq!(DevProperty::U8Array(Vec::new()).display_smart(), "[]");
+        assert_eq!(DevProperty::Binary(Vec::new()).display_smart(), "");
+        assert_eq!(DevProperty::U32(7).display_smart(), "7");
+    }
+
+    #[test]
+    fn integers_widen_to_i128() {
+        let values = [
+            (DevProperty::I8(i8::MIN), i128::from(i8::MIN)),
+            (DevProperty::U8(u8::MAX), i128::from(u8::MAX)),
+            (DevProperty::I16(i16::MIN), i128::from(i16::MIN)),
+            (DevProperty::U16(u16::MAX), i128::from(u16::MAX)),
+            (DevProperty::I32(i32::MIN), i128::from(i32::MIN)),
+            (DevProperty::U32(u32::MAX), i128::from(u32::MAX)),
+            (DevProperty::I64(i64::MIN), i128::from(i64::MIN)),
+            (DevProperty::U64(u64::MAX), 18_446_744_073_709_551_615),
+        ];
+        for (value, expected) in values {
+            assert_eq!(value.as_i128(), Some(expected), "{value:?}");
+        }
+        assert_eq!(DevProperty::F64(1.0).as_i128(), None);
+        assert_eq!(DevProperty::U32Array(vec![1]).as_i128(), None);
+    }
+
+    #[test]
+    fn clone_each_variant() {
+        let values = [
+            DevProperty::Empty,
+            DevProperty::Bool(true),
+            DevProperty::String("SD Card".into()),
+            DevProperty::StringList(vec!["a".into(), "b".into()]),
+            DevProperty::I8Array(vec![-1, 2]),
+            DevProperty::U8Array(vec![1, 2]),
+            DevProperty::I16Array(vec![-1, 2]),
+            DevProperty::U16Array(vec![1, 2]),
+            DevProperty::I32Array(vec![-1, 2]),
+            DevProperty::U32Array(vec![1, 2]),
+            DevProperty::I64Array(vec![-1, 2]),
+            DevProperty::U64Array(vec![1, 2]),
+            DevProperty::F32Array(vec![-1.5, 2.0]),
+            DevProperty::F64Array(vec![-1.5, 2.0]),
+            DevProperty::Binary(vec![0xde, 0xad]),
+            DevProperty::Guid(guid("53f56307-b6bf-11d0-94f2-00a0c91efb8b")),
+            DevProperty::GuidArray(vec![guid("53f56307-b6bf-11d0-94f2-00a0c91efb8b")]),
+            DevProperty::SecurityDescriptor(vec![1, 0]),
+            DevProperty::Unsupported {
+                ty: 0x7f,
+                data: Box::new([1, 2, 3]),
+            },
+        ];
+        for value in values {
+            assert_eq!(value.clone(), value);
+        }
+
+        // NOTE: the heap buffers are copied, not shared
+        let binary = DevProperty::Binary(vec![0xde, 0xad]);
+        if let (DevProperty::Binary(a), DevProperty::Binary(b)) = (&binary, &binary.clone()) {
+            assert_ne!(a.as_ptr(), b.as_ptr());
+        }
+    }
+
+    #[test]
+    fn equality_by_value() {
+        let a = GuidWrap(GUID {
+            Data1: 0x53f5_6307,
+            Data2: 0xb6bf,
+            Data3: 0x11d0,
+            Data4: [0x94, 0xf2, 0x00, 0xa0, 0xc9, 0x1e, 0xfb, 0x8b],
+        });
+        let b = GuidWrap(GUID {
+            Data1: 0x53f5_630d,
+            ..a.0
+        });
+        assert_eq!(DevProperty::Guid(a), DevProperty::Guid(a));
+        assert_ne!(DevProperty::Guid(a), DevProperty::Guid(b));
+
+        let array = DevProperty::U32Array(vec![1, 2]);
+        assert_eq!(array, DevProperty::U32Array(vec![1, 2]));
+        assert_ne!(array, DevProperty::U32Array(vec![2, 1]));
+        assert_ne!(array, DevProperty::U64Array(vec![1, 2]));
+
+        let string = DevProperty::String("SD".into());
+        assert_eq!(string, DevProperty::String("SD".into()));
+        assert_ne!(string, DevProperty::String("sd".into()));
+        assert_ne!(string, DevProperty::U8Array(b"SD".to_vec()));
+    }
+
+    #[test]
+    fn floats_are_compared_bitwise() {
+        assert_eq!(DevProperty::F64(f64::NAN), DevProperty::F64(f64::NAN));
+        assert_ne!(DevProperty::F64(0.0), DevProperty::F64(-0.0));
+        assert_eq!(DevProperty::F32(1.5), DevProperty::F32(1.5));
+        assert_ne!(DevProperty::F32(f32::NAN), DevProperty::F32(-f32::NAN));
+        assert_eq!(
+            DevProperty::F32Array(vec![f32::NAN, 0.0]),
+            DevProperty::F32Array(vec![f32::NAN, 0.0])
+        );
+        assert_ne!(
+            DevProperty::F64Array(vec![0.0]),
+            DevProperty::F64Array(vec![-0.0])
+        );
+    }
+
+    #[test]
+    fn guid_parse_round_trip() {
+        let guids = [
+            "53f56307-b6bf-11d0-94f2-00a0c91efb8b",
+            "53f5630d-b6bf-11d0-94f2-00a0c91efb8b",
+            "6ac27878-a6fa-4155-ba85-f98f491d4f33",
+            "86e0d1e0-8089-11d0-9ce4-08003e301f73",
+        ];
+        for s in guids {
+            let parsed = guid(s);
+            assert_eq!(parsed.to_string(), s);
+            assert_eq!(parsed.to_string().parse(), Ok(parsed));
+            assert_eq!(format!("{{{}}}", s.to_uppercase()).parse(), Ok(parsed));
+        }
+    }
+
+    #[test]
+    fn guid_parse_errors() {
+        let parse = |s: &str| s.parse::<GuidWrap>();
+        assert_eq!(
+            parse("{53f56307-b6bf-11d0-94f2-00a0c91efb8b"),
+            Err(GuidParseError::UnmatchedBrace)
+        );
+        assert_eq!(
+            parse("53f56307-b6bf-11d0-94f2-00a0c91efb8b}"),
+            Err(GuidParseError::UnmatchedBrace)
+        );
+        assert_eq!(
+            parse("53f56307-b6bf-11d0-94f2-00a0c91efb8"),
+            Err(GuidParseError::InvalidLength(35))
+        );
+        assert_eq!(parse(""), Err(GuidParseError::InvalidLength(0)));
+        assert_eq!(
+            parse("53f56307_b6bf-11d0-94f2-00a0c91efb8b"),
+            Err(GuidParseError::InvalidSeparator(8))
+        );
+        assert_eq!(
+            parse("53f56307-b6bf-11d0-94f2-00a0c91efb8g"),
+            Err(GuidParseError::InvalidDigit(35))
+        );
+        assert_eq!(
+            parse("+3f56307-b6bf-11d0-94f2-00a0c91efb8b"),
+            Err(GuidParseError::InvalidDigit(0))
+        );
+    }
+
+    #[test]
+    fn guid_case_and_braces() {
+        let disk = guid("53f56307-b6bf-11d0-94f2-00a0c91efb8b");
+        assert_eq!(format!("{disk}"), "53f56307-b6bf-11d0-94f2-00a0c91efb8b");
+        assert_eq!(
+            format!("{disk:#}"),
+            "{53f56307-b6bf-11d0-94f2-00a0c91efb8b}"
+        );
+        assert_eq!(format!("{disk:X}"), "53F56307-B6BF-11D0-94F2-00A0C91EFB8B");
+        assert_eq!(
+            format!("{disk:#X}"),
+            "{53F56307-B6BF-11D0-94F2-00A0C91EFB8B}"
+        );
+    }
+
+    #[test]
+    fn canonical_guid_is_lowercase() {
+        let disk = guid("{53F56307-B6BF-11D0-94F2-00A0C91EFB8B}");
+        assert_eq!(
+            disk.to_canonical_string(),
+            "53f56307-b6bf-11d0-94f2-00a0c91efb8b"
+        );
+        assert_eq!(
+            DevProperty::Guid(disk).guid_string().as_deref(),
+            Some("53f56307-b6bf-11d0-94f2-00a0c91efb8b")
+        );
+        assert_eq!(DevProperty::U32(0).guid_string(), None);
+    }
+
+    #[test]
+    fn scalars_of_arrays() {
+        let array = DevProperty::I16Array(vec![-1, 0, 300]);
+        let scalars: Vec<_> = array.iter_scalars().collect();
+        assert_eq!(
+            scalars,
+            [
+                DevProperty::I16(-1),
+                DevProperty::I16(0),
+                DevProperty::I16(300)
+            ]
+        );
+
+        let scalar = DevProperty::Bool(true);
+        assert_eq!(scalar.iter_scalars().collect::<Vec<_>>(), [scalar]);
+        assert_eq!(DevProperty::I16Array(Vec::new()).iter_scalars().count(), 0);
+    }
+
+    #[test]
+    fn integer_array_display() {
+        let array = DevProperty::U32Array(vec![1, 0xab, u32::MAX]);
+        assert_eq!(format!("{array}"), "[1, 171, 4294967295]");
+        assert_eq!(format!("{array:#}"), "0x00000001\n0x000000ab\n0xffffffff");
+    }
+
+    #[test]
+    fn binary_display() {
+        let binary = DevProperty::Binary(vec![0xde, 0xad, 0xbe, 0xef, 0x01, 0x02]);
+        assert_eq!(format!("{binary}"), "deadbeef0102");
+        assert_eq!(format!("{binary:#}"), "DE AD BE EF 01 02");
+        assert_eq!(format!("{binary:#.4}"), "DE AD BE EF\n01 02");
+    }
+
+    #[test]
+    fn currency_is_scaled() {
+        assert_eq!(DevProperty::Currency(12_345).to_string(), "1.2345");
+        assert_eq!(DevProperty::Currency(-5).to_string(), "-0.0005");
+        assert_eq!(DevProperty::Currency(10_000 * 42).to_string(), "42.0000");
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_value_shapes() {
+        use serde_json::json;
+
+        let array = DevProperty::U64Array(vec![1, 1 << 53, u64::MAX]);
+        assert_eq!(
+            array.to_json_value(),
+            json!([1, "9007199254740992", "18446744073709551615"])
+        );
+
+        let disk = DevProperty::Guid(guid("53f56307-b6bf-11d0-94f2-00a0c91efb8b"));
+        assert_eq!(
+            disk.to_json_value(),
+            json!("53f56307-b6bf-11d0-94f2-00a0c91efb8b")
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use winapi::shared::minwindef::DWORD;
+use winapi::shared::winerror::{
+    ERROR_ACCESS_DENIED, ERROR_INSUFFICIENT_BUFFER, ERROR_INTERNAL_ERROR, ERROR_INVALID_DATA,
+    ERROR_INVALID_DATATYPE, ERROR_MORE_DATA, ERROR_NOT_ENOUGH_MEMORY, ERROR_NOT_FOUND,
+    ERROR_NO_MORE_ITEMS,
+};
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::setupapi::ERROR_NO_SUCH_DEVICE_INTERFACE;
+
+/// A Win32 error code, like the ones returned by [`GetLastError()`]
+///
+/// The [`Debug`](std::fmt::Debug) output names the code when it's one of the constants,
+/// e.g. `INSUFFICIENT_BUFFER (0x7a)`
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Error(DWORD);
+
+impl Error {
+    /// `ERROR_INSUFFICIENT_BUFFER`: the data area passed to a system call is too small
+    pub const INSUFFICIENT_BUFFER: Self = Self(ERROR_INSUFFICIENT_BUFFER);
+    /// `ERROR_NO_MORE_ITEMS`: no more data is available
+    pub const NO_MORE_ITEMS: Self = Self(ERROR_NO_MORE_ITEMS);
+    /// `ERROR_NOT_FOUND`: element not found
+    ///
+    /// Returned when fetching a property that is not set on the device
+    pub const NOT_FOUND: Self = Self(ERROR_NOT_FOUND);
+    /// `ERROR_NOT_ENOUGH_MEMORY`: not enough memory resources are available to process this command
+    ///
+    /// Returned by this crate when a buffer of the size reported by the WinAPI can't be allocated
+    pub const NOT_ENOUGH_MEMORY: Self = Self(ERROR_NOT_ENOUGH_MEMORY);
+    /// `ERROR_INVALID_DATA`: the data is invalid
+    ///
+    /// Returned by this crate when a value doesn't have the size expected for its type,
+    /// or when a device interface path doesn't have the expected form
+    pub const INVALID_DATA: Self = Self(ERROR_INVALID_DATA);
+    /// `ERROR_INVALID_DATATYPE`: the data type is invalid
+    ///
+    /// Returned by this crate when a value doesn't have the expected type
+    pub const INVALID_DATATYPE: Self = Self(ERROR_INVALID_DATATYPE);
+    /// `ERROR_MORE_DATA`: more data is available
+    ///
+    /// Returned by this crate when reading a single string from a property holding a list of them
+    /// (see [`DevInterfaceData::fetch_string_list()`](crate::devset::DevInterfaceData::fetch_string_list))
+    pub const MORE_DATA: Self = Self(ERROR_MORE_DATA);
+    /// `ERROR_ACCESS_DENIED`: access is denied
+    ///
+    /// Returned when opening a device, or the devices of a remote machine, without the privileges
+    pub const ACCESS_DENIED: Self = Self(ERROR_ACCESS_DENIED);
+    /// `ERROR_NO_SUCH_DEVICE_INTERFACE`: the device interface doesn't exist
+    ///
+    /// Returned when querying a device interface that has been removed in the meantime
+    pub const NO_SUCH_DEVICE_INTERFACE: Self = Self(ERROR_NO_SUCH_DEVICE_INTERFACE);
+    /// `ERROR_INTERNAL_ERROR`: an internal error occurred
+    ///
+    /// Returned by this crate when a WinAPI function returns something it's not expected to
+    pub const INTERNAL_ERROR: Self = Self(ERROR_INTERNAL_ERROR);
+
+    /// The constants paired with their names, to print them
+    const NAMED: [(Self, &'static str); 10] = [
+        (Self::INSUFFICIENT_BUFFER, "INSUFFICIENT_BUFFER"),
+        (Self::NO_MORE_ITEMS, "NO_MORE_ITEMS"),
+        (Self::NOT_FOUND, "NOT_FOUND"),
+        (Self::NOT_ENOUGH_MEMORY, "NOT_ENOUGH_MEMORY"),
+        (Self::INVALID_DATA, "INVALID_DATA"),
+        (Self::INVALID_DATATYPE, "INVALID_DATATYPE"),
+        (Self::MORE_DATA, "MORE_DATA"),
+        (Self::ACCESS_DENIED, "ACCESS_DENIED"),
+        (Self::NO_SUCH_DEVICE_INTERFACE, "NO_SUCH_DEVICE_INTERFACE"),
+        (Self::INTERNAL_ERROR, "INTERNAL_ERROR"),
+    ];
+
+    /// Returns the last error code of the calling thread
+    pub fn get() -> Self {
+        // SAFETY: how can this be unsafe?
+        Self(unsafe { GetLastError() })
+    }
+
+    /// Constructs the error from its raw Win32 code
+    pub const fn from_code(code: u32) -> Self {
+        Self(code)
+    }
+
+    /// Returns the raw Win32 code of the error
+    pub const fn code(&self) -> u32 {
+        self.0
+    }
+}
+
+impl std::fmt::Debug for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match Self::NAMED.iter().find(|(err, _)| err == self) {
+            Some((_, name)) => write!(f, "{name} ({:#x})", self.0),
+            None => write!(f, "Error({:#x})", self.0),
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Win32 error {:#x}", self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A [`Result`](std::result::Result) whose error is a Win32 [`Error`]
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_names_known_codes() {
+        assert_eq!(
+            format!("{:?}", Error::INSUFFICIENT_BUFFER),
+            "INSUFFICIENT_BUFFER (0x7a)"
+        );
+        assert_eq!(format!("{:?}", Error::NOT_FOUND), "NOT_FOUND (0x490)");
+        assert_eq!(format!("{:?}", Error::from_code(0x1234)), "Error(0x1234)");
+        assert_eq!(Error::from_code(0x7a), Error::INSUFFICIENT_BUFFER);
+        assert_eq!(Error::INSUFFICIENT_BUFFER.code(), 0x7a);
+    }
+}